@@ -1,76 +1,28 @@
-use std::fs;
-
-struct Machine {
-    pub program: Vec<i32>,
-    pub counter: usize,
-    pub finished: bool,
-}
-
-impl Machine {
-    pub fn new(program: &[i32]) -> Self {
-        Machine {
-            program: program.to_owned(),
-            counter: 0,
-            finished: false,
-        }
-    }
-
-    pub fn run(&mut self, params: &[i32]) -> i32 {
-        for (index, param) in params.iter().enumerate() {
-            self.program[index + 1] = *param;
-        }
-
-        while !self.finished {
-            let code = self.program[self.counter];
-
-            match code {
-                1 => {
-                    let index_1 = self.program[self.counter + 1] as usize;
-                    let index_2 = self.program[self.counter + 2] as usize;
-                    let index_3 = self.program[self.counter + 3] as usize;
-
-                    self.program[index_3] = self.program[index_1] + self.program[index_2];
-
-                    self.counter += 4;
-                }
-                2 => {
-                    let index_1 = self.program[self.counter + 1] as usize;
-                    let index_2 = self.program[self.counter + 2] as usize;
-                    let index_3 = self.program[self.counter + 3] as usize;
-
-                    self.program[index_3] = self.program[index_1] * self.program[index_2];
-
-                    self.counter += 4;
-                }
-                99 => {
-                    self.finished = true;
-                }
-                _ => {
-                    panic!("Unknown optcode: {} at index: {}", code, self.counter);
-                }
-            }
-        }
-
-        self.program[0]
-    }
-}
-
-fn main() -> std::io::Result<()> {
-    let program: Vec<i32> = fs::read_to_string("input")?
-        .split(',')
-        .filter_map(|s| s.parse::<i32>().ok())
-        .collect();
-
-    for noun in 0..=99 {
-        for verb in 0..=99 {
-            let mut machine = Machine::new(&program);
-            let result = machine.run(&[noun, verb]);
-
-            if result == 19_690_720 {
-                println!("Result is: {}", 100 * noun + verb);
-                break;
-            }
-        }
+use intcode::{MachineResult, PatchedRun};
+
+fn main() -> MachineResult<()> {
+    let program = intcode::parse_file("input")?;
+
+    let result = PatchedRun::new(&program)
+        .patch(1, 12)
+        .patch(2, 2)
+        .read(&[0])
+        .run()?;
+    println!("Result is: {}", result.values[0]);
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    // A patch that makes the program loop must not stall the search.
+    let found = PatchedRun::new(&program)
+        .read(&[0])
+        .max_steps(100_000)
+        .search(&[(1, 0..=99), (2, 0..=99)], threads, |result| {
+            result.values[0] == 19_690_720
+        })?;
+
+    if let Some(found) = found {
+        let noun = found.patches[0].1;
+        let verb = found.patches[1].1;
+        println!("Result is: {}", 100 * noun + verb);
     }
 
     Ok(())
@@ -13,7 +13,6 @@ impl From<std::io::Error> for MachineError {
     }
 }
 
-#[must_use]
 pub type MachineResult<T> = Result<T, MachineError>;
 
 #[derive(Debug)]
@@ -31,8 +30,8 @@ impl OpCode {
     }
 
     pub fn mode(&self, pos: i32) -> i32 {
-        let position = (10 as i32).pow(pos as u32 + 1);
-        (self.code / position % 10)
+        let position = 10_i32.pow(pos as u32 + 1);
+        self.code / position % 10
     }
 }
 
@@ -128,7 +127,7 @@ impl Machine {
                 eprintln!("Exception at index {}!", index);
                 let slice = &self.program[index..index + 4];
                 eprintln!("Program: {:?}", slice);
-                eprintln!("{:?}", error);
+                eprintln!("{}", error.message);
                 return false;
             }
         }
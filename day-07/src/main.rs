@@ -1,15 +1,16 @@
-mod intmachine;
+use intcode::{
+    Machine, MachineResult, Pipeline, PipelineMode, RecordInterface, ReplayInterface, RunOutcome,
+    Topology,
+};
 
-use intmachine::{Interface, Machine, MachineResult};
-
-fn permute(vec: Vec<i32>, place: usize) -> Vec<Vec<i32>> {
+fn permute(vec: Vec<i128>, place: usize) -> Vec<Vec<i128>> {
     if place >= vec.len() {
         vec![vec]
     } else {
-        let mut result: Vec<Vec<i32>> = Vec::new();
+        let mut result: Vec<Vec<i128>> = Vec::new();
 
         for index in 0..vec.len() {
-            let index = index as i32;
+            let index = index as i128;
 
             let mut cont = false;
             for item in vec.iter().take(place) {
@@ -32,37 +33,65 @@ fn permute(vec: Vec<i32>, place: usize) -> Vec<Vec<i32>> {
     }
 }
 
-fn main() -> MachineResult<()> {
-    let program = intmachine::parse_file("input")?;
+fn pipeline(program: &[i128], phases: &[i128]) -> Pipeline {
+    let mut pipeline = Pipeline::new(program, PipelineMode::Feedback).stage(&[phases[0], 0]);
+    for phase in phases.iter().skip(1) {
+        pipeline = pipeline.stage(&[*phase]);
+    }
+    pipeline
+}
 
-    let mut max_signal: i32 = 0;
-    let mut max_phases: Vec<i32> = vec![];
+fn transcript_path(directory: &str, stage: usize) -> String {
+    format!("{}/stage-{}.transcript", directory, stage)
+}
 
-    let phases_permutation = permute(vec![0, 1, 2, 3, 4], 0);
+// Every amplifier is checked on its own against its transcript. The last
+// output of the last stage is the signal.
+fn replay(program: &[i128], directory: &str) -> MachineResult<()> {
+    let mut machine = Machine::new(program);
 
-    let mut machine = Machine::new(&program);
+    for stage in 0..5 {
+        let interface = ReplayInterface::from_file(&transcript_path(directory, stage))?;
+        if let RunOutcome::Failed(error) = machine.spawn(Box::new(interface)).join() {
+            println!("Stage {} failed: {} {}", stage, error.message, error.reason);
+            return Ok(());
+        }
+    }
 
-    for phases in phases_permutation {
-        let phases: Vec<i32> = phases.iter().map(|v| v + 5).collect();
-        let mut param = 0;
-        let mut last_iteration = -1;
+    println!("Replay of {} matches", directory);
+    Ok(())
+}
 
-        let mut machines: Vec<Interface> = phases.iter().map(|_| machine.spawn()).collect();
+fn main() -> MachineResult<()> {
+    let program = intcode::parse_file("input")?;
+
+    let mut args = std::env::args().skip(1);
+    let (command, argument) = (args.next(), args.next());
+    match (command.as_deref(), argument.as_deref()) {
+        (Some("replay"), Some(directory)) => return replay(&program, directory),
+        (Some(path), None) => {
+            let topology = Topology::parse_file(path)?;
+            let last_outputs = Machine::new(&program).run_network(&topology)?;
+
+            for (node, output) in topology.nodes.iter().zip(last_outputs) {
+                println!("{}: {:?}", node.name, output);
+            }
 
-        for (index, (sender, _)) in machines.iter_mut().enumerate() {
-            sender.send(phases[index])?;
+            return Ok(());
         }
+        _ => {}
+    }
 
-        while last_iteration != param {
-            last_iteration = param;
-            for (sender, receiver) in machines.iter_mut() {
-                if sender.send(param).is_err() {
-                    break;
-                }
+    let mut max_signal: i128 = 0;
+    let mut max_phases: Vec<i128> = vec![];
 
-                param = receiver.recv().unwrap_or(last_iteration);
-            }
-        }
+    let phases_permutation = permute(vec![0, 1, 2, 3, 4], 0);
+
+    for phases in phases_permutation {
+        let phases: Vec<i128> = phases.iter().map(|v| v + 5).collect();
+
+        let pipeline = pipeline(&program, &phases);
+        let param = pipeline.run(pipeline.len() - 1)?;
 
         if param > max_signal {
             max_signal = param;
@@ -72,5 +101,14 @@ fn main() -> MachineResult<()> {
 
     println!("Signal: {}, Phases: {:?}", max_signal, max_phases);
 
+    // Records the best run, one transcript per amplifier.
+    if let (Some("record"), Some(directory)) = (command.as_deref(), argument.as_deref()) {
+        let pipeline = pipeline(&program, &max_phases);
+        pipeline.run_with(pipeline.len() - 1, |stage, interface| {
+            let path = transcript_path(directory, stage);
+            Ok(Box::new(RecordInterface::new(interface, &path)?))
+        })?;
+    }
+
     Ok(())
 }
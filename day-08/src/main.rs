@@ -39,14 +39,7 @@ fn main() -> std::io::Result<()> {
 
     println!("prod: {}", one_count * two_count);
 
-    let mut result: Vec<Vec<u8>> = Vec::new();
-    for _ in 0..height {
-        let mut v: Vec<u8> = Vec::new();
-        for _ in 0..width {
-            v.push(0);
-        }
-        result.push(v);
-    }
+    let mut result: Vec<Vec<u8>> = vec![vec![0; width]; height];
 
     for layer in layers.iter().rev() {
         for row in 0..height {
@@ -0,0 +1,42 @@
+// Times the sensor boost of day 9 in the interpreter and as transpiled
+// Rust. Run with `cargo bench -p day-09`.
+use std::time::{Duration, Instant};
+
+use intcode::{parse_file, PatchedRun, QueueInterface};
+
+#[allow(clippy::all, dead_code)]
+mod boost {
+    include!(concat!(env!("OUT_DIR"), "/boost.rs"));
+}
+
+const ROUNDS: u32 = 10;
+
+fn time<F: FnMut() -> Vec<i128>>(mut run: F) -> (Duration, Vec<i128>) {
+    let output = run();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        run();
+    }
+    (start.elapsed() / ROUNDS, output)
+}
+
+fn main() {
+    let program = parse_file(concat!(env!("CARGO_MANIFEST_DIR"), "/input")).unwrap();
+
+    let (interpreted, expected) =
+        time(|| PatchedRun::new(&program).input(&[2]).run().unwrap().output);
+    let (transpiled, output) = time(|| {
+        let interface = QueueInterface::new();
+        interface.push_input(&[2]);
+        boost::run(Box::new(interface.clone()));
+        interface.take_output()
+    });
+    assert_eq!(output, expected);
+
+    println!("interpreter: {:?} per run", interpreted);
+    println!("transpiled:  {:?} per run", transpiled);
+    println!(
+        "speedup:     {:.1}x",
+        interpreted.as_secs_f64() / transpiled.as_secs_f64()
+    );
+}
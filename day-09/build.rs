@@ -0,0 +1,24 @@
+// Outputs 5, then multiplies beyond an i128.
+const PRODUCT_OVERFLOW: [i128; 7] = [104, 5, 1102, i128::MAX, 2, 20, 99];
+// Moves the relative base to the end of an i128, then one further.
+const RELATIVE_OVERFLOW: [i128; 5] = [109, i128::MAX, 109, 1, 99];
+
+// Transpiles the puzzle input, so the tests and the bench can compare the
+// native code with the interpreter, and two programs that overflow.
+fn main() {
+    println!("cargo:rerun-if-changed=input");
+
+    let program = intcode::parse_file("input").expect("day 9 input");
+    let out = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let write = |name: &str, program: &[i128]| {
+        std::fs::write(
+            format!("{}/{}.rs", out, name),
+            intcode::transpile(program, &[]),
+        )
+        .expect("writable OUT_DIR");
+    };
+
+    write("boost", &program);
+    write("product_overflow", &PRODUCT_OVERFLOW);
+    write("relative_overflow", &RELATIVE_OVERFLOW);
+}
@@ -1,14 +1,13 @@
-mod intmachine;
-
-use intmachine::{Machine, MachineResult};
+use intcode::{MachineResult, PatchedRun};
 
 fn main() -> MachineResult<()> {
-    //let program = intmachine::parse("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
-    let program = intmachine::parse_file("input")?;
-
-    let mut machine = Machine::new(&program);
+    let program = intcode::parse_file("input")?;
 
-    machine.spawn();
+    // Input 1 runs the self test, input 2 the sensor boost.
+    for mode in 1..=2 {
+        let result = PatchedRun::new(&program).input(&[mode]).run()?;
+        println!("Output for {}: {:?}", mode, result.output);
+    }
 
     Ok(())
 }
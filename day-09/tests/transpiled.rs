@@ -0,0 +1,76 @@
+use intcode::{parse_file, Executer, MachineInterface, PatchedRun, QueueInterface, RunOutcome};
+
+#[allow(clippy::all, dead_code)]
+mod boost {
+    include!(concat!(env!("OUT_DIR"), "/boost.rs"));
+}
+
+#[allow(clippy::all, dead_code)]
+mod product_overflow {
+    include!(concat!(env!("OUT_DIR"), "/product_overflow.rs"));
+}
+
+#[allow(clippy::all, dead_code)]
+mod relative_overflow {
+    include!(concat!(env!("OUT_DIR"), "/relative_overflow.rs"));
+}
+
+fn transpiled(input: i128) -> Vec<i128> {
+    let interface = QueueInterface::new();
+    interface.push_input(&[input]);
+
+    match boost::run(Box::new(interface.clone())) {
+        RunOutcome::Halted => interface.take_output(),
+        outcome => panic!("transpiled run stopped as {:?}", outcome),
+    }
+}
+
+fn interpreted(input: i128) -> Vec<i128> {
+    let program = parse_file(concat!(env!("CARGO_MANIFEST_DIR"), "/input")).unwrap();
+    PatchedRun::new(&program)
+        .input(&[input])
+        .run()
+        .unwrap()
+        .output
+}
+
+#[test]
+fn self_test_matches_the_interpreter() {
+    assert_eq!(transpiled(1), vec![4_006_117_640]);
+    assert_eq!(transpiled(1), interpreted(1));
+}
+
+#[test]
+fn sensor_boost_matches_the_interpreter() {
+    assert_eq!(transpiled(2), vec![88231]);
+    assert_eq!(transpiled(2), interpreted(2));
+}
+
+// Both modes fail with the same error and the same output before it.
+fn assert_same_failure(run: fn(Box<dyn MachineInterface>) -> RunOutcome, program: &[i128]) {
+    let native = QueueInterface::new();
+    let native_outcome = run(Box::new(native.clone()));
+
+    let interpreter = QueueInterface::new();
+    let interpreted_outcome =
+        Executer::new(0, program, Box::new(interpreter.clone()), false).execute();
+
+    match (native_outcome, interpreted_outcome) {
+        (RunOutcome::Failed(native_error), RunOutcome::Failed(interpreted_error)) => {
+            assert_eq!(native_error.message, "Arithmetic overflow!");
+            assert_eq!(native_error.reason, interpreted_error.reason);
+        }
+        outcomes => panic!("expected two failures, got {:?}", outcomes),
+    }
+    assert_eq!(native.take_output(), interpreter.take_output());
+}
+
+#[test]
+fn product_overflow_fails_like_the_interpreter() {
+    assert_same_failure(product_overflow::run, &[104, 5, 1102, i128::MAX, 2, 20, 99]);
+}
+
+#[test]
+fn relative_overflow_fails_like_the_interpreter() {
+    assert_same_failure(relative_overflow::run, &[109, i128::MAX, 109, 1, 99]);
+}
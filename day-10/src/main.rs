@@ -41,7 +41,7 @@ fn build_distance_map(center: (i32, i32), data: &[Vec<char>]) -> DegreeVec {
         let angle = (get_degree(center, coord) * 100.0) as i32;
         let distance = (get_distance(center, coord) * 100.0) as i32;
 
-        degree_map.entry(angle).or_insert_with(HashMap::new);
+        degree_map.entry(angle).or_default();
 
         degree_map.get_mut(&angle).unwrap().insert(distance, coord);
     }
@@ -1,6 +1,6 @@
-mod intmachine;
-
-use intmachine::{Machine, MachineInterface, MachineResult};
+use intcode::{
+    Machine, MachineInterface, MachineResult, RecordInterface, ReplayInterface, RunOutcome,
+};
 
 use std::collections::HashSet;
 
@@ -118,21 +118,30 @@ impl MachineInterface for Map {
         Ok(())
     }
 
-    fn receive(&mut self) -> MachineResult<i128> {
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
         let value = self.color_map.contains(&self.position) as i128;
-        Ok(value)
+        Ok(Some(value))
     }
 }
 
 fn main() -> MachineResult<()> {
-    //let program = intmachine::parse("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
-    let program = intmachine::parse_file("input")?;
+    //let program = intcode::parse("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
+    let program = intcode::parse_file("input")?;
 
-    let map = Map::new();
+    let mut args = std::env::args().skip(1);
+    let interface: Box<dyn MachineInterface> = match (args.next().as_deref(), args.next()) {
+        (Some("record"), Some(path)) => {
+            Box::new(RecordInterface::new(Box::new(Map::new()), &path)?)
+        }
+        (Some("replay"), Some(path)) => Box::new(ReplayInterface::from_file(&path)?),
+        _ => Box::new(Map::new()),
+    };
 
     let mut machine = Machine::new(&program);
 
-    machine.spawn(Box::new(map));
+    if let RunOutcome::Failed(error) = machine.spawn(interface).join() {
+        println!("Run failed: {:?}", error);
+    }
 
     Ok(())
 }
@@ -0,0 +1,69 @@
+#ifndef INTCODE_H
+#define INTCODE_H
+
+/*
+ * C interface to the Intcode VM, link against libintcode_ffi.
+ *
+ * Values are 64 bit integers, reading one that grew beyond that returns
+ * INTCODE_OUT_OF_RANGE instead of a value. Every function taking a machine accepts the
+ * pointers intcode_new returned, until they are passed to intcode_free,
+ * and NULL. An internal error of the library is reported as a failure
+ * with its message in intcode_error.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct IntcodeMachine IntcodeMachine;
+
+/* Returned by intcode_step and intcode_run. */
+#define INTCODE_HALTED 0
+/* Waiting for input, push some and run again. */
+#define INTCODE_BLOCKED 1
+/* A single step is done. */
+#define INTCODE_PAUSED 2
+/* See intcode_error. */
+#define INTCODE_FAILED 3
+/* Returned instead of a value that does not fit into 64 bits. */
+#define INTCODE_OUT_OF_RANGE (-1)
+
+/* Copies the program, returns NULL if it is NULL but length is not 0. */
+IntcodeMachine *intcode_new(const int64_t *program, size_t length);
+void intcode_free(IntcodeMachine *machine);
+
+void intcode_push_input(IntcodeMachine *machine, int64_t value);
+
+int intcode_step(IntcodeMachine *machine);
+/* Runs until the program halts, fails or needs more input. */
+int intcode_run(IntcodeMachine *machine);
+
+size_t intcode_output_count(IntcodeMachine *machine);
+/*
+ * Takes the oldest output, returns 0 if there is none. An output out of
+ * range is taken as well.
+ */
+int intcode_pop_output(IntcodeMachine *machine, int64_t *value);
+
+/* Both return 0 if the address cannot be accessed. */
+int intcode_read_memory(const IntcodeMachine *machine, int64_t address, int64_t *value);
+int intcode_write_memory(IntcodeMachine *machine, int64_t address, int64_t value);
+
+/* Return 1, or 0 if machine or the target is NULL. */
+int intcode_counter(const IntcodeMachine *machine, int64_t *counter);
+int intcode_relative(const IntcodeMachine *machine, int64_t *relative);
+
+/*
+ * The message of the last failure, NULL if there was none. It stays valid
+ * until the machine fails again or is freed.
+ */
+const char *intcode_error(const IntcodeMachine *machine);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,245 @@
+// C interface to the Intcode executer, declared in `include/intcode.h`.
+// Values cross the boundary as 64 bit integers; reading one that grew
+// beyond that returns `INTCODE_OUT_OF_RANGE` instead of a value.
+//
+// Every function taking a machine accepts the pointers `intcode_new`
+// returned until they are passed to `intcode_free`, and null. A panic
+// never crosses the boundary, it is reported like a failed run.
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+
+use intcode::{Executer, QueueInterface, RunOutcome};
+
+pub const INTCODE_HALTED: c_int = 0;
+// Waiting for input.
+pub const INTCODE_BLOCKED: c_int = 1;
+pub const INTCODE_PAUSED: c_int = 2;
+pub const INTCODE_FAILED: c_int = 3;
+// Returned instead of a value that does not fit into 64 bits.
+pub const INTCODE_OUT_OF_RANGE: c_int = -1;
+
+pub struct IntcodeMachine {
+    executer: Executer,
+    interface: QueueInterface,
+    output: VecDeque<i128>,
+    error: Option<CString>,
+}
+
+impl IntcodeMachine {
+    fn status(&mut self, outcome: RunOutcome) -> c_int {
+        match outcome {
+            RunOutcome::Halted => INTCODE_HALTED,
+            RunOutcome::Exhausted => INTCODE_BLOCKED,
+            RunOutcome::Paused | RunOutcome::Cancelled => INTCODE_PAUSED,
+            RunOutcome::Failed(error) => {
+                let message = format!("{} {}", error.message, error.reason);
+                self.error = CString::new(message).ok();
+                INTCODE_FAILED
+            }
+        }
+    }
+}
+
+// Stores `wide` in `value` if it fits and returns 1.
+fn narrow(wide: i128, value: &mut i64) -> c_int {
+    match i64::try_from(wide) {
+        Ok(narrow) => {
+            *value = narrow;
+            1
+        }
+        Err(_) => INTCODE_OUT_OF_RANGE,
+    }
+}
+
+// Runs `body`, or returns `fallback` if it panicked and keeps the message
+// for `intcode_error`.
+unsafe fn guard<T, F>(machine: *const IntcodeMachine, fallback: T, body: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let payload = match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => return value,
+        Err(payload) => payload,
+    };
+
+    let reason = match (
+        payload.downcast_ref::<&str>(),
+        payload.downcast_ref::<String>(),
+    ) {
+        (Some(reason), _) => reason.to_string(),
+        (_, Some(reason)) => reason.clone(),
+        _ => "Unknown reason.".to_owned(),
+    };
+    if let Some(machine) = (machine as *mut IntcodeMachine).as_mut() {
+        machine.error = CString::new(format!("Panic! {}", reason)).ok();
+    }
+    fallback
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_new(program: *const i64, length: usize) -> *mut IntcodeMachine {
+    guard(std::ptr::null(), std::ptr::null_mut(), || {
+        let program: Vec<i128> = if length == 0 {
+            Vec::new()
+        } else if program.is_null() {
+            return std::ptr::null_mut();
+        } else {
+            std::slice::from_raw_parts(program, length)
+                .iter()
+                .map(|value| *value as i128)
+                .collect()
+        };
+
+        let interface = QueueInterface::new();
+        let machine = IntcodeMachine {
+            executer: Executer::new(0, &program, Box::new(interface.clone()), false),
+            interface,
+            output: VecDeque::new(),
+            error: None,
+        };
+        Box::into_raw(Box::new(machine))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_free(machine: *mut IntcodeMachine) {
+    guard(std::ptr::null(), (), || {
+        if !machine.is_null() {
+            drop(Box::from_raw(machine));
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_push_input(machine: *mut IntcodeMachine, value: i64) {
+    guard(machine, (), || {
+        if let Some(machine) = machine.as_mut() {
+            machine.interface.push_input(&[value as i128]);
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_step(machine: *mut IntcodeMachine) -> c_int {
+    guard(machine, INTCODE_FAILED, || match machine.as_mut() {
+        Some(machine) => {
+            let outcome = machine.executer.step();
+            machine.status(outcome)
+        }
+        None => INTCODE_FAILED,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_run(machine: *mut IntcodeMachine) -> c_int {
+    guard(machine, INTCODE_FAILED, || match machine.as_mut() {
+        Some(machine) => {
+            let outcome = machine.executer.execute();
+            machine.status(outcome)
+        }
+        None => INTCODE_FAILED,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_output_count(machine: *mut IntcodeMachine) -> usize {
+    guard(machine, 0, || match machine.as_mut() {
+        Some(machine) => {
+            let output = machine.interface.take_output();
+            machine.output.extend(output);
+            machine.output.len()
+        }
+        None => 0,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_pop_output(
+    machine: *mut IntcodeMachine,
+    value: *mut i64,
+) -> c_int {
+    guard(machine, 0, || {
+        let machine = match machine.as_mut() {
+            Some(machine) => machine,
+            None => return 0,
+        };
+        let output = machine.interface.take_output();
+        machine.output.extend(output);
+
+        match (machine.output.pop_front(), value.as_mut()) {
+            // An output out of range is dropped, so the next one can be read.
+            (Some(output), Some(value)) => narrow(output, value),
+            (Some(output), None) => {
+                machine.output.push_front(output);
+                0
+            }
+            (None, _) => 0,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_read_memory(
+    machine: *const IntcodeMachine,
+    address: i64,
+    value: *mut i64,
+) -> c_int {
+    guard(machine, 0, || match (machine.as_ref(), value.as_mut()) {
+        (Some(machine), Some(value)) => match machine.executer.get(address as i128) {
+            Ok(cell) => narrow(cell, value),
+            Err(_) => 0,
+        },
+        _ => 0,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_write_memory(
+    machine: *mut IntcodeMachine,
+    address: i64,
+    value: i64,
+) -> c_int {
+    guard(machine, 0, || match machine.as_mut() {
+        Some(machine) => machine.executer.set(address as i128, value as i128).is_ok() as c_int,
+        None => 0,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_counter(
+    machine: *const IntcodeMachine,
+    counter: *mut i64,
+) -> c_int {
+    guard(machine, 0, || match (machine.as_ref(), counter.as_mut()) {
+        (Some(machine), Some(counter)) => narrow(machine.executer.counter(), counter),
+        _ => 0,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn intcode_relative(
+    machine: *const IntcodeMachine,
+    relative: *mut i64,
+) -> c_int {
+    guard(machine, 0, || match (machine.as_ref(), relative.as_mut()) {
+        (Some(machine), Some(relative)) => narrow(machine.executer.relative(), relative),
+        _ => 0,
+    })
+}
+
+// The message of the last failure, null if there was none. It stays valid
+// until the machine fails again or is freed.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_error(machine: *const IntcodeMachine) -> *const c_char {
+    guard(std::ptr::null(), std::ptr::null(), || {
+        machine
+            .as_ref()
+            .and_then(|machine| machine.error.as_ref())
+            .map_or(std::ptr::null(), |error| error.as_ptr())
+    })
+}
@@ -0,0 +1,87 @@
+use std::ffi::CStr;
+
+use intcode_ffi::*;
+
+const TWO_TO_62: i64 = 1 << 62;
+
+unsafe fn run(program: &[i64]) -> (i32, String) {
+    let machine = intcode_new(program.as_ptr(), program.len());
+    let status = intcode_run(machine);
+    let error = intcode_error(machine);
+    let message = if error.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(error).to_string_lossy().into_owned()
+    };
+    intcode_free(machine);
+    (status, message)
+}
+
+#[test]
+fn output_of_a_run() {
+    let program = [3, 0, 1002, 0, 2, 0, 4, 0, 99];
+    unsafe {
+        let machine = intcode_new(program.as_ptr(), program.len());
+        assert_eq!(intcode_run(machine), INTCODE_BLOCKED);
+        intcode_push_input(machine, 21);
+        assert_eq!(intcode_run(machine), INTCODE_HALTED);
+
+        let mut value = 0;
+        assert_eq!(intcode_pop_output(machine, &mut value), 1);
+        assert_eq!(value, 42);
+        intcode_free(machine);
+    }
+}
+
+#[test]
+fn overflow_fails_the_run() {
+    // 2^62 * 2^62 * 8 does not fit into an i128.
+    let program = [1102, TWO_TO_62, TWO_TO_62, 100, 1002, 100, 8, 100, 99];
+    let (status, message) = unsafe { run(&program) };
+    assert_eq!(status, INTCODE_FAILED);
+    assert!(message.starts_with("Arithmetic overflow!"), "{}", message);
+}
+
+#[test]
+fn counter_at_the_end_of_the_address_space_fails_the_run() {
+    let program = [
+        // [100] = 2^126, [101] = 2^127 - 1
+        1102, TWO_TO_62, TWO_TO_62, 100, 1002, 100, 4, 100, 1001, 100, -1, 101, 1, 101, 100, 101,
+        // Writes an add to [101] and jumps there, its parameters are past
+        // the last address.
+        1001, 101, 0, 23, 1101, 1, 0, 0, 105, 1, 101,
+    ];
+    let (status, message) = unsafe { run(&program) };
+    assert_eq!(status, INTCODE_FAILED);
+    assert!(message.starts_with("Arithmetic overflow!"), "{}", message);
+}
+
+#[test]
+fn values_beyond_64_bits_are_out_of_range() {
+    // Outputs 2^62 * 4 and stores it in [100], then moves the relative
+    // base there.
+    let program = [
+        1102, TWO_TO_62, 4, 100, 4, 100, 109, TWO_TO_62, 109, TWO_TO_62, 109, TWO_TO_62, 109,
+        TWO_TO_62, 104, 7, 99,
+    ];
+    unsafe {
+        let machine = intcode_new(program.as_ptr(), program.len());
+        assert_eq!(intcode_run(machine), INTCODE_HALTED);
+
+        let mut value = 0;
+        assert_eq!(
+            intcode_pop_output(machine, &mut value),
+            INTCODE_OUT_OF_RANGE
+        );
+        assert_eq!(intcode_pop_output(machine, &mut value), 1);
+        assert_eq!(value, 7);
+        assert_eq!(
+            intcode_read_memory(machine, 100, &mut value),
+            INTCODE_OUT_OF_RANGE
+        );
+        assert_eq!(intcode_relative(machine, &mut value), INTCODE_OUT_OF_RANGE);
+        assert_eq!(intcode_counter(machine, &mut value), 1);
+        assert_eq!(value, 16);
+        intcode_free(machine);
+    }
+}
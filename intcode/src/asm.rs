@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+use super::{mnemonic, Instruction, MachineError, MachineResult, OpCode, Param};
+
+#[derive(Debug, Clone)]
+pub struct Assembly {
+    pub program: Vec<i128>,
+    // Address of every instruction or data word -> line in the source,
+    // starting at 1.
+    pub lines: BTreeMap<i128, usize>,
+    pub labels: BTreeMap<String, i128>,
+}
+
+impl Assembly {
+    // The source line the word at `address` came from.
+    pub fn line(&self, address: i128) -> Option<usize> {
+        self.lines
+            .range(..=address)
+            .next_back()
+            .map(|(_, line)| *line)
+    }
+
+    // The first address generated by `line`.
+    pub fn address(&self, line: usize) -> Option<i128> {
+        self.lines
+            .iter()
+            .find(|(_, l)| **l == line)
+            .map(|(address, _)| *address)
+    }
+}
+
+fn asm_error(line: usize, reason: String) -> MachineError {
+    MachineError {
+        message: "Illegal assembly!".to_owned(),
+        reason: format!("Line {}: {}", line, reason),
+    }
+}
+
+fn opcode(name: &str) -> Option<i128> {
+    (1..=9)
+        .chain(Some(99))
+        .find(|op| mnemonic(*op) == Some(name))
+}
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '.' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') && name != "rb"
+}
+
+struct Line<'a> {
+    number: usize,
+    address: i128,
+    name: &'a str,
+    operands: Vec<&'a str>,
+}
+
+fn value(labels: &BTreeMap<String, i128>, line: usize, text: &str) -> MachineResult<i128> {
+    let text = text.trim();
+    if let Ok(value) = text.parse::<i128>() {
+        return Ok(value);
+    }
+
+    let (label, offset) = match text.rfind(['+', '-']) {
+        Some(index) if index > 0 => {
+            let offset = text[index..]
+                .replace(' ', "")
+                .parse::<i128>()
+                .map_err(|_| asm_error(line, format!("Illegal offset in '{}'.", text)))?;
+            (text[..index].trim(), offset)
+        }
+        _ => (text, 0),
+    };
+
+    labels
+        .get(label)
+        .map(|address| address + offset)
+        .ok_or_else(|| asm_error(line, format!("Unknown label '{}'.", label)))
+}
+
+fn operand(labels: &BTreeMap<String, i128>, line: usize, text: &str) -> MachineResult<Param> {
+    let text = text.trim();
+    if !text.starts_with('[') {
+        return Ok(Param::Immediate(value(labels, line, text)?));
+    }
+    if !text.ends_with(']') {
+        return Err(asm_error(line, format!("Missing ']' in '{}'.", text)));
+    }
+
+    let inner = text[1..text.len() - 1].trim();
+    if inner == "rb" {
+        return Ok(Param::Relative(0));
+    }
+    if let Some(offset) = inner.strip_prefix("rb") {
+        let offset = offset.replace(' ', "");
+        return offset
+            .parse::<i128>()
+            .map(Param::Relative)
+            .map_err(|_| asm_error(line, format!("Illegal offset in '{}'.", text)));
+    }
+    Ok(Param::Position(value(labels, line, inner)?))
+}
+
+// Assembles the listings the disassembler prints: one instruction per line,
+// `[a]` for position, `[rb+o]` for relative and plain values for immediate
+// parameters, `data v, ...` for raw words. Lines may start with `label:`,
+// labels can be used wherever a value is expected, and `;` starts a
+// comment.
+pub fn assemble(source: &str) -> MachineResult<Assembly> {
+    let mut labels = BTreeMap::new();
+    let mut parsed = Vec::new();
+    let mut address = 0;
+
+    for (index, text) in source.lines().enumerate() {
+        let number = index + 1;
+        let mut text = text.split(';').next().unwrap_or("").trim();
+
+        // Listings start with the address, it is recomputed anyway.
+        if let Some(space) = text.find(char::is_whitespace) {
+            if text[..space].parse::<i128>().is_ok() {
+                text = text[space..].trim();
+            }
+        }
+
+        while let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if !is_label(label) {
+                return Err(asm_error(number, format!("Illegal label '{}'.", label)));
+            }
+            if labels.insert(label.to_owned(), address).is_some() {
+                return Err(asm_error(number, format!("Duplicate label '{}'.", label)));
+            }
+            text = text[colon + 1..].trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (name, rest) = match text.find(char::is_whitespace) {
+            Some(space) => (&text[..space], text[space..].trim()),
+            None => (text, ""),
+        };
+        let operands: Vec<&str> = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',').map(|o| o.trim()).collect()
+        };
+
+        let length = if name == "data" {
+            operands.len() as i128
+        } else {
+            let op = opcode(name)
+                .ok_or_else(|| asm_error(number, format!("Unknown instruction '{}'.", name)))?;
+            let length = OpCode::new(op).length().unwrap_or(1);
+            if operands.len() as i128 != length - 1 {
+                return Err(asm_error(
+                    number,
+                    format!("'{}' takes {} operands.", name, length - 1),
+                ));
+            }
+            length
+        };
+
+        parsed.push(Line {
+            number,
+            address,
+            name,
+            operands,
+        });
+        address += length;
+    }
+
+    let mut program = Vec::new();
+    let mut lines = BTreeMap::new();
+
+    for line in parsed {
+        lines.insert(line.address, line.number);
+
+        if line.name == "data" {
+            for text in line.operands {
+                program.push(value(&labels, line.number, text)?);
+            }
+            continue;
+        }
+
+        let instruction = Instruction {
+            address: line.address,
+            op: opcode(line.name).unwrap_or(99),
+            params: line
+                .operands
+                .iter()
+                .map(|text| operand(&labels, line.number, text))
+                .collect::<MachineResult<Vec<Param>>>()?,
+        };
+        if let Some(target) = instruction.target() {
+            if let Param::Immediate(_) = instruction.params[target] {
+                return Err(asm_error(
+                    line.number,
+                    format!("'{}' cannot write to an immediate.", line.name),
+                ));
+            }
+        }
+
+        program.extend(instruction.encode());
+    }
+
+    Ok(Assembly {
+        program,
+        lines,
+        labels,
+    })
+}
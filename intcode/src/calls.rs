@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use super::{Executer, Observer, OpCode, RunOutcome};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    // Entry address of the called function.
+    pub function: i128,
+    pub call_site: i128,
+    pub return_address: i128,
+    // Relative base right after the call.
+    pub relative: i128,
+}
+
+impl std::fmt::Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} called from {}, returns to {}, rb {}",
+            self.function, self.call_site, self.return_address, self.relative
+        )
+    }
+}
+
+// How many of the last written values are checked for a return address.
+const RECENT_WRITES: usize = 4;
+
+#[derive(Debug, Default)]
+struct CallStackData {
+    frames: Vec<Frame>,
+    // The last values written, a call pushes its return address right
+    // before it jumps.
+    written: Vec<i128>,
+    // Whether the last instruction raised the relative base.
+    raised: bool,
+    // Address, fall-through, whether the target is immediate and whether
+    // the base was raised right before, of the jump that ran last.
+    jump: Option<(i128, i128, bool, bool)>,
+    // Function entries from the outermost frame -> executed instructions.
+    samples: BTreeMap<Vec<i128>, u64>,
+    pending: u64,
+}
+
+impl CallStackData {
+    fn flush(&mut self) {
+        if self.pending > 0 {
+            let key = self.frames.iter().map(|f| f.function).collect();
+            *self.samples.entry(key).or_default() += self.pending;
+            self.pending = 0;
+        }
+    }
+
+    fn jumped(&mut self, executer: &Executer) {
+        let (address, next, immediate, raised) = match self.jump.take() {
+            Some(jump) => jump,
+            None => return,
+        };
+        let counter = executer.counter();
+        if counter == next {
+            return;
+        }
+
+        if immediate && (raised || self.written.contains(&next)) {
+            self.flush();
+            self.written.clear();
+            self.frames.push(Frame {
+                function: counter,
+                call_site: address,
+                return_address: next,
+                relative: executer.relative(),
+            });
+        } else if !immediate {
+            if let Some(index) = self
+                .frames
+                .iter()
+                .rposition(|f| f.return_address == counter)
+            {
+                self.flush();
+                self.frames.truncate(index);
+            }
+        }
+    }
+
+    // Lowering the base below the frame of a function leaves it, even if
+    // the return jump was not recognised.
+    fn lowered(&mut self, relative: i128) {
+        if self.frames.last().is_some_and(|f| f.relative > relative) {
+            self.flush();
+            while self.frames.last().is_some_and(|f| f.relative > relative) {
+                self.frames.pop();
+            }
+        }
+    }
+}
+
+// Infers calls and returns from the usual calling convention: a call
+// writes its return address or raises the relative base, then jumps to an
+// immediate target; a return jumps to a target read from memory, usually
+// `[rb+0]`, or lowers the base below the frame. Clones share their data,
+// so the stack can be read while the executer runs.
+#[derive(Debug, Clone, Default)]
+pub struct CallStack {
+    data: Arc<Mutex<CallStackData>>,
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        CallStack::default()
+    }
+
+    // Resolves the last jump for an executer that stopped without asking
+    // its observers, as after a single step.
+    pub fn update(&self, executer: &Executer) {
+        self.data.lock().unwrap().jumped(executer);
+    }
+
+    // Innermost frame last.
+    pub fn frames(&self) -> Vec<Frame> {
+        self.data.lock().unwrap().frames.clone()
+    }
+
+    // One line per distinct stack with the number of instructions executed
+    // in it, the format flamegraph tools read. Functions are shown by the
+    // name in `names`, otherwise by their address; the program itself is
+    // the outermost frame.
+    pub fn folded(&self, names: &BTreeMap<i128, String>) -> String {
+        let mut data = self.data.lock().unwrap();
+        data.flush();
+
+        let name = |address: i128| {
+            names
+                .get(&address)
+                .cloned()
+                .unwrap_or_else(|| format!("@{}", address))
+        };
+
+        data.samples
+            .iter()
+            .map(|(stack, count)| {
+                let mut path = vec![name(0)];
+                path.extend(stack.iter().map(|address| name(*address)));
+                format!("{} {}", path.join(";"), count)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Observer for CallStack {
+    fn instruction(&mut self, executer: &Executer, code: &OpCode) {
+        let mut data = self.data.lock().unwrap();
+        data.jumped(executer);
+        data.pending += 1;
+        let raised = std::mem::take(&mut data.raised);
+
+        if code.op() == 5 || code.op() == 6 {
+            let counter = executer.counter();
+            data.jump = Some((
+                counter,
+                counter.saturating_add(3),
+                code.mode(2) == 1,
+                raised,
+            ));
+        }
+    }
+
+    fn relative(&mut self, executer: &Executer, relative: i128) {
+        let mut data = self.data.lock().unwrap();
+        data.raised = relative > executer.relative();
+        data.lowered(relative);
+    }
+
+    fn write(&mut self, _executer: &Executer, _address: i128, value: i128) {
+        let mut data = self.data.lock().unwrap();
+        if data.written.len() == RECENT_WRITES {
+            data.written.remove(0);
+        }
+        data.written.push(value);
+    }
+
+    // Resolves the last jump already here, so a debugger that pauses at a
+    // function entry sees the new frame.
+    fn pause(&mut self, executer: &Executer) -> bool {
+        self.data.lock().unwrap().jumped(executer);
+        false
+    }
+
+    fn halt(&mut self, _executer: &Executer, _outcome: &RunOutcome) {
+        self.data.lock().unwrap().flush();
+    }
+}
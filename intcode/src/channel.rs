@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::{MachineError, MachineResult, RunOutcome};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+    Unbounded,
+    // The sender blocks while the queue holds `capacity` values.
+    Bounded(usize),
+    // The sender never blocks, a full queue discards its oldest value.
+    DropOldest(usize),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub depth: usize,
+    pub max_depth: usize,
+    pub sent: u64,
+    pub received: u64,
+    pub dropped: u64,
+    pub send_blocked: Duration,
+    pub receive_blocked: Duration,
+}
+
+#[derive(Debug)]
+struct State {
+    queue: VecDeque<i128>,
+    senders: usize,
+    receivers: usize,
+    closed: bool,
+    end: Option<RunOutcome>,
+    stats: ChannelStats,
+}
+
+#[derive(Debug)]
+struct Shared {
+    mode: ChannelMode,
+    state: Mutex<State>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panicking peer cannot leave the queue half updated, so the
+        // poisoned state is still usable.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn closed(action: &str) -> MachineError {
+    MachineError {
+        message: "Channel closed!".to_owned(),
+        reason: format!("Cannot {}, the other side of the channel is gone.", action),
+    }
+}
+
+pub fn channel_with_mode(mode: ChannelMode) -> (ChannelSender, ChannelReceiver) {
+    let mode = match mode {
+        ChannelMode::Bounded(capacity) => ChannelMode::Bounded(capacity.max(1)),
+        ChannelMode::DropOldest(capacity) => ChannelMode::DropOldest(capacity.max(1)),
+        ChannelMode::Unbounded => ChannelMode::Unbounded,
+    };
+
+    let shared = Arc::new(Shared {
+        mode,
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            senders: 1,
+            receivers: 1,
+            closed: false,
+            end: None,
+            stats: ChannelStats::default(),
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+
+    (
+        ChannelSender {
+            shared: shared.clone(),
+        },
+        ChannelReceiver { shared },
+    )
+}
+
+#[derive(Debug)]
+pub struct ChannelSender {
+    shared: Arc<Shared>,
+}
+
+impl ChannelSender {
+    pub fn send(&self, value: i128) -> MachineResult<()> {
+        let mut state = self.shared.lock();
+
+        if let ChannelMode::Bounded(capacity) = self.shared.mode {
+            while state.queue.len() >= capacity && state.receivers > 0 && !state.closed {
+                let start = Instant::now();
+                state = self
+                    .shared
+                    .not_full
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner());
+                state.stats.send_blocked += start.elapsed();
+            }
+        }
+
+        if state.receivers == 0 || state.closed {
+            return Err(closed("send"));
+        }
+
+        if let ChannelMode::DropOldest(capacity) = self.shared.mode {
+            while state.queue.len() >= capacity {
+                state.queue.pop_front();
+                state.stats.dropped += 1;
+            }
+        }
+
+        state.queue.push_back(value);
+        state.stats.sent += 1;
+        state.stats.max_depth = state.stats.max_depth.max(state.queue.len());
+        self.shared.not_empty.notify_one();
+
+        Ok(())
+    }
+
+    // Ends the stream. The receiver still gets all queued values and then
+    // sees the end of the stream.
+    pub fn close(&self) {
+        self.shared.lock().closed = true;
+        self.shared.not_empty.notify_all();
+        self.shared.not_full.notify_all();
+    }
+
+    // Ends the stream like `close` and attaches the exit status of the
+    // machine that wrote into it.
+    pub fn halt(&self, outcome: RunOutcome) {
+        let mut state = self.shared.lock();
+        if !state.closed {
+            state.closed = true;
+            state.end = Some(outcome);
+        }
+        self.shared.not_empty.notify_all();
+        self.shared.not_full.notify_all();
+    }
+
+    pub fn stats(&self) -> ChannelStats {
+        let state = self.shared.lock();
+        ChannelStats {
+            depth: state.queue.len(),
+            ..state.stats
+        }
+    }
+}
+
+impl Clone for ChannelSender {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        ChannelSender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for ChannelSender {
+    fn drop(&mut self) {
+        self.shared.lock().senders -= 1;
+        self.shared.not_empty.notify_all();
+    }
+}
+
+#[derive(Debug)]
+pub struct ChannelReceiver {
+    shared: Arc<Shared>,
+}
+
+impl ChannelReceiver {
+    // Returns `None` once the stream has ended, either because a sender
+    // closed it or because all senders are gone.
+    pub fn recv(&self) -> Option<i128> {
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(value) = state.queue.pop_front() {
+                state.stats.received += 1;
+                self.shared.not_full.notify_one();
+                return Some(value);
+            }
+
+            if state.senders == 0 || state.closed {
+                return None;
+            }
+
+            let start = Instant::now();
+            state = self
+                .shared
+                .not_empty
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+            state.stats.receive_blocked += start.elapsed();
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<i128> {
+        let mut state = self.shared.lock();
+        let value = state.queue.pop_front();
+
+        if value.is_some() {
+            state.stats.received += 1;
+            self.shared.not_full.notify_one();
+        }
+        value
+    }
+
+    // The exit status the stream was halted with, `None` while it is open or
+    // if it ended without one.
+    pub fn end_status(&self) -> Option<RunOutcome> {
+        self.shared.lock().end.clone()
+    }
+
+    pub fn stats(&self) -> ChannelStats {
+        let state = self.shared.lock();
+        ChannelStats {
+            depth: state.queue.len(),
+            ..state.stats
+        }
+    }
+}
+
+impl Clone for ChannelReceiver {
+    fn clone(&self) -> Self {
+        self.shared.lock().receivers += 1;
+        ChannelReceiver {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for ChannelReceiver {
+    fn drop(&mut self) {
+        self.shared.lock().receivers -= 1;
+        self.shared.not_full.notify_all();
+    }
+}
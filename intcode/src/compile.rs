@@ -0,0 +1,723 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::{assemble, Assembly, MachineError, MachineResult};
+
+fn compile_error(line: usize, reason: String) -> MachineError {
+    MachineError {
+        message: "Compile error!".to_owned(),
+        reason: format!("Line {}: {}", line, reason),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i128),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "&&", "||", "(", ")", "{", "}", ",", ";", "=", "+", "-", "*", "<", ">",
+    "!",
+];
+
+fn tokenize(source: &str) -> MachineResult<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let text = text.split("//").next().unwrap_or("");
+        let chars: Vec<char> = text.chars().collect();
+        let mut position = 0;
+
+        while position < chars.len() {
+            let c = chars[position];
+            if c.is_whitespace() {
+                position += 1;
+            } else if c.is_ascii_digit() {
+                let start = position;
+                while position < chars.len() && chars[position].is_ascii_digit() {
+                    position += 1;
+                }
+                let digits: String = chars[start..position].iter().collect();
+                let value = digits
+                    .parse()
+                    .map_err(|_| compile_error(line, format!("Number {} is too big.", digits)))?;
+                tokens.push((line, Token::Number(value)));
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let start = position;
+                while position < chars.len()
+                    && (chars[position].is_ascii_alphanumeric() || chars[position] == '_')
+                {
+                    position += 1;
+                }
+                tokens.push((line, Token::Name(chars[start..position].iter().collect())));
+            } else {
+                let rest: String = chars[position..].iter().take(2).collect();
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|s| rest.starts_with(*s))
+                    .ok_or_else(|| compile_error(line, format!("Unexpected '{}'.", c)))?;
+                tokens.push((line, Token::Symbol(symbol)));
+                position += symbol.len();
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Expression {
+    Number(i128),
+    Variable(String),
+    Call(String, Vec<Expression>),
+    Unary(&'static str, Box<Expression>),
+    Binary(&'static str, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone)]
+enum Statement {
+    Let(String, Expression),
+    Assign(String, Expression),
+    If(Expression, Vec<(usize, Statement)>, Vec<(usize, Statement)>),
+    While(Expression, Vec<(usize, Statement)>),
+    Return(Option<Expression>),
+    Expression(Expression),
+}
+
+#[derive(Debug, Clone)]
+struct Function {
+    line: usize,
+    name: String,
+    params: Vec<String>,
+    body: Vec<(usize, Statement)>,
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(line, _)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> MachineResult<Token> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| compile_error(self.line(), "Unexpected end of file.".to_owned()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn accept(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Name(name)) if name == keyword => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> MachineResult<()> {
+        let line = self.line();
+        match self.next()? {
+            Token::Symbol(s) if s == symbol => Ok(()),
+            token => Err(compile_error(
+                line,
+                format!("Expected '{}', found '{}'.", symbol, token),
+            )),
+        }
+    }
+
+    fn name(&mut self) -> MachineResult<String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Name(name) if !is_keyword(&name) => Ok(name),
+            token => Err(compile_error(
+                line,
+                format!("Expected a name, found '{}'.", token),
+            )),
+        }
+    }
+
+    fn function(&mut self) -> MachineResult<Function> {
+        let line = self.line();
+        if !self.accept_keyword("fn") {
+            return Err(compile_error(line, "Expected 'fn'.".to_owned()));
+        }
+
+        let name = self.name()?;
+        self.expect("(")?;
+        let mut params = Vec::new();
+        while !self.accept(")") {
+            if !params.is_empty() {
+                self.expect(",")?;
+            }
+            params.push(self.name()?);
+        }
+
+        Ok(Function {
+            line,
+            name,
+            params,
+            body: self.block()?,
+        })
+    }
+
+    fn block(&mut self) -> MachineResult<Vec<(usize, Statement)>> {
+        self.expect("{")?;
+        let mut statements = Vec::new();
+        while !self.accept("}") {
+            statements.push((self.line(), self.statement()?));
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> MachineResult<Statement> {
+        if self.accept_keyword("let") {
+            let name = self.name()?;
+            self.expect("=")?;
+            let value = self.expression()?;
+            self.expect(";")?;
+            return Ok(Statement::Let(name, value));
+        }
+
+        if self.accept_keyword("if") {
+            let condition = self.expression()?;
+            let then = self.block()?;
+            let otherwise = if self.accept_keyword("else") {
+                if let Some(Token::Name(name)) = self.peek() {
+                    if name == "if" {
+                        let line = self.line();
+                        return Ok(Statement::If(
+                            condition,
+                            then,
+                            vec![(line, self.statement()?)],
+                        ));
+                    }
+                }
+                self.block()?
+            } else {
+                Vec::new()
+            };
+            return Ok(Statement::If(condition, then, otherwise));
+        }
+
+        if self.accept_keyword("while") {
+            let condition = self.expression()?;
+            return Ok(Statement::While(condition, self.block()?));
+        }
+
+        if self.accept_keyword("return") {
+            if self.accept(";") {
+                return Ok(Statement::Return(None));
+            }
+            let value = self.expression()?;
+            self.expect(";")?;
+            return Ok(Statement::Return(Some(value)));
+        }
+
+        let is_assignment = matches!(
+            (self.peek(), self.tokens.get(self.position + 1)),
+            (Some(Token::Name(_)), Some((_, Token::Symbol("="))))
+        );
+        if is_assignment {
+            let name = self.name()?;
+            self.expect("=")?;
+            let value = self.expression()?;
+            self.expect(";")?;
+            return Ok(Statement::Assign(name, value));
+        }
+
+        let expression = self.expression()?;
+        self.expect(";")?;
+        Ok(Statement::Expression(expression))
+    }
+
+    fn expression(&mut self) -> MachineResult<Expression> {
+        self.binary(0)
+    }
+
+    // Operators by precedence, lowest first.
+    fn binary(&mut self, level: usize) -> MachineResult<Expression> {
+        const LEVELS: [&[&str]; 5] = [
+            &["||"],
+            &["&&"],
+            &["==", "!=", "<", "<=", ">", ">="],
+            &["+", "-"],
+            &["*"],
+        ];
+
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = LEVELS[level].iter().find(|op| self.accept(op)) {
+            let right = self.binary(level + 1)?;
+            left = Expression::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> MachineResult<Expression> {
+        for op in &["-", "!"] {
+            if self.accept(op) {
+                return Ok(Expression::Unary(op, Box::new(self.unary()?)));
+            }
+        }
+
+        let line = self.line();
+        match self.next()? {
+            Token::Number(value) => Ok(Expression::Number(value)),
+            Token::Symbol("(") => {
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Token::Name(name) if !is_keyword(&name) => {
+                if !self.accept("(") {
+                    return Ok(Expression::Variable(name));
+                }
+                let mut args = Vec::new();
+                while !self.accept(")") {
+                    if !args.is_empty() {
+                        self.expect(",")?;
+                    }
+                    args.push(self.expression()?);
+                }
+                Ok(Expression::Call(name, args))
+            }
+            token => Err(compile_error(line, format!("Unexpected '{}'.", token))),
+        }
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    ["fn", "let", "if", "else", "while", "return"].contains(&name)
+}
+
+// Where a value lives: an immediate or a slot in the current frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Constant(i128),
+    Slot(i128),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Value::Constant(value) => write!(f, "{}", value),
+            Value::Slot(slot) => write!(f, "[rb+{}]", slot),
+        }
+    }
+}
+
+// Every function gets a frame at the relative base: slot 0 holds the
+// return address, slot 1 the return value, then the parameters, the
+// locals and the temporaries. A call places the callee's frame right above
+// the slots the caller uses at that point.
+struct Generator<'a> {
+    functions: &'a HashMap<String, usize>,
+    source: Vec<&'a str>,
+    output: String,
+    labels: usize,
+    scopes: Vec<HashMap<String, i128>>,
+    next: i128,
+}
+
+impl<'a> Generator<'a> {
+    fn emit(&mut self, line: String) {
+        let _ = writeln!(self.output, "    {}", line);
+    }
+
+    fn label(&mut self, name: &str) -> String {
+        self.labels += 1;
+        format!(".{}{}", name, self.labels)
+    }
+
+    fn place(&mut self, label: &str) {
+        let _ = writeln!(self.output, "{}:", label);
+    }
+
+    fn temp(&mut self) -> i128 {
+        self.next += 1;
+        self.next - 1
+    }
+
+    fn lookup(&self, line: usize, name: &str) -> MachineResult<i128> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+            .ok_or_else(|| compile_error(line, format!("Unknown variable '{}'.", name)))
+    }
+
+    fn function(&mut self, function: &Function) -> MachineResult<()> {
+        let _ = writeln!(self.output, "\n{}:", function.name);
+        let params = function
+            .params
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), index as i128 + 2))
+            .collect();
+        self.scopes = vec![params];
+        self.next = function.params.len() as i128 + 2;
+
+        self.block(&function.body)?;
+        self.emit("add 0, 0, [rb+1]".to_owned());
+        self.emit("jt 1, [rb+0]".to_owned());
+        Ok(())
+    }
+
+    fn block(&mut self, statements: &[(usize, Statement)]) -> MachineResult<()> {
+        let next = self.next;
+        self.scopes.push(HashMap::new());
+
+        for (line, statement) in statements {
+            if let Some(text) = self.source.get(line - 1) {
+                let _ = writeln!(self.output, "    ; {}: {}", line, text.trim());
+            }
+            self.statement(*line, statement)?;
+        }
+
+        self.scopes.pop();
+        self.next = next;
+        Ok(())
+    }
+
+    fn statement(&mut self, line: usize, statement: &Statement) -> MachineResult<()> {
+        let next = self.next;
+
+        match statement {
+            Statement::Let(name, value) => {
+                let value = self.expression(line, value)?;
+                self.next = next + 1;
+                if value != Value::Slot(next) {
+                    self.emit(format!("add {}, 0, [rb+{}]", value, next));
+                }
+                self.scopes.last_mut().unwrap().insert(name.clone(), next);
+                return Ok(());
+            }
+            Statement::Assign(name, value) => {
+                let slot = self.lookup(line, name)?;
+                let value = self.expression(line, value)?;
+                self.emit(format!("add {}, 0, [rb+{}]", value, slot));
+            }
+            Statement::If(condition, then, otherwise) => {
+                let otherwise_label = self.label("else");
+                let end = self.label("end");
+
+                let condition = self.expression(line, condition)?;
+                self.next = next;
+                self.emit(format!("jf {}, {}", condition, otherwise_label));
+                self.block(then)?;
+                if !otherwise.is_empty() {
+                    self.emit(format!("jt 1, {}", end));
+                }
+                self.place(&otherwise_label);
+                self.block(otherwise)?;
+                self.place(&end);
+            }
+            Statement::While(condition, body) => {
+                let start = self.label("while");
+                let end = self.label("end");
+
+                self.place(&start);
+                let condition = self.expression(line, condition)?;
+                self.next = next;
+                self.emit(format!("jf {}, {}", condition, end));
+                self.block(body)?;
+                self.emit(format!("jt 1, {}", start));
+                self.place(&end);
+            }
+            Statement::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expression(line, value)?,
+                    None => Value::Constant(0),
+                };
+                self.emit(format!("add {}, 0, [rb+1]", value));
+                self.emit("jt 1, [rb+0]".to_owned());
+            }
+            Statement::Expression(expression) => {
+                self.expression(line, expression)?;
+            }
+        }
+
+        self.next = next;
+        Ok(())
+    }
+
+    fn expression(&mut self, line: usize, expression: &Expression) -> MachineResult<Value> {
+        match expression {
+            Expression::Number(value) => Ok(Value::Constant(*value)),
+            Expression::Variable(name) => Ok(Value::Slot(self.lookup(line, name)?)),
+            Expression::Call(name, args) => self.call(line, name, args),
+            Expression::Unary(op, value) => {
+                let value = self.expression(line, value)?;
+                if let Value::Constant(value) = value {
+                    if let Some(value) = unary(op, value) {
+                        return Ok(Value::Constant(value));
+                    }
+                }
+
+                let result = self.temp();
+                match *op {
+                    "-" => self.emit(format!("mul {}, -1, [rb+{}]", value, result)),
+                    _ => self.emit(format!("eq {}, 0, [rb+{}]", value, result)),
+                }
+                Ok(Value::Slot(result))
+            }
+            Expression::Binary(op, left, right) if *op == "&&" || *op == "||" => {
+                self.logic(line, op, left, right)
+            }
+            Expression::Binary(op, left, right) => {
+                let result = self.temp();
+                let left = self.expression(line, left)?;
+                let right = self.expression(line, right)?;
+                self.next = result + 1;
+
+                if let (Value::Constant(a), Value::Constant(b)) = (left, right) {
+                    if let Some(value) = binary(op, a, b) {
+                        self.next = result;
+                        return Ok(Value::Constant(value));
+                    }
+                }
+
+                let target = format!("[rb+{}]", result);
+                match *op {
+                    "+" => self.emit(format!("add {}, {}, {}", left, right, target)),
+                    "*" => self.emit(format!("mul {}, {}, {}", left, right, target)),
+                    "-" => {
+                        self.emit(format!("mul {}, -1, {}", right, target));
+                        self.emit(format!("add {}, {}, {}", left, target, target));
+                    }
+                    "==" => self.emit(format!("eq {}, {}, {}", left, right, target)),
+                    "!=" => {
+                        self.emit(format!("eq {}, {}, {}", left, right, target));
+                        self.emit(format!("eq {}, 0, {}", target, target));
+                    }
+                    "<" => self.emit(format!("lt {}, {}, {}", left, right, target)),
+                    ">" => self.emit(format!("lt {}, {}, {}", right, left, target)),
+                    "<=" => {
+                        self.emit(format!("lt {}, {}, {}", right, left, target));
+                        self.emit(format!("eq {}, 0, {}", target, target));
+                    }
+                    _ => {
+                        self.emit(format!("lt {}, {}, {}", left, right, target));
+                        self.emit(format!("eq {}, 0, {}", target, target));
+                    }
+                }
+                Ok(Value::Slot(result))
+            }
+        }
+    }
+
+    // `&&` and `||` only evaluate the right side when needed.
+    fn logic(
+        &mut self,
+        line: usize,
+        op: &str,
+        left: &Expression,
+        right: &Expression,
+    ) -> MachineResult<Value> {
+        let result = self.temp();
+        let short = self.label("short");
+        let end = self.label("end");
+        let jump = if op == "&&" { "jf" } else { "jt" };
+
+        for side in &[left, right] {
+            let value = self.expression(line, side)?;
+            self.next = result + 1;
+            self.emit(format!("{} {}, {}", jump, value, short));
+        }
+
+        let (full, shortcut) = if op == "&&" { (1, 0) } else { (0, 1) };
+        self.emit(format!("add {}, 0, [rb+{}]", full, result));
+        self.emit(format!("jt 1, {}", end));
+        self.place(&short);
+        self.emit(format!("add {}, 0, [rb+{}]", shortcut, result));
+        self.place(&end);
+        Ok(Value::Slot(result))
+    }
+
+    fn call(&mut self, line: usize, name: &str, args: &[Expression]) -> MachineResult<Value> {
+        match (name, args.len()) {
+            ("input", 0) => {
+                let result = self.temp();
+                self.emit(format!("in [rb+{}]", result));
+                return Ok(Value::Slot(result));
+            }
+            ("output", 1) => {
+                let value = self.expression(line, &args[0])?;
+                self.emit(format!("out {}", value));
+                return Ok(Value::Constant(0));
+            }
+            ("input", _) | ("output", _) => {
+                return Err(compile_error(
+                    line,
+                    format!("Wrong number of arguments for '{}'.", name),
+                ));
+            }
+            _ => {}
+        }
+
+        match self.functions.get(name) {
+            Some(count) if *count == args.len() => {}
+            Some(_) => {
+                return Err(compile_error(
+                    line,
+                    format!("Wrong number of arguments for '{}'.", name),
+                ))
+            }
+            None => return Err(compile_error(line, format!("Unknown function '{}'.", name))),
+        }
+
+        // The callee's frame: return address, return value, arguments.
+        let frame = self.next;
+        self.next += 2 + args.len() as i128;
+        for (index, arg) in args.iter().enumerate() {
+            let value = self.expression(line, arg)?;
+            self.emit(format!(
+                "add {}, 0, [rb+{}]",
+                value,
+                frame + 2 + index as i128
+            ));
+        }
+
+        let back = self.label("return");
+        self.emit(format!("add {}, 0, [rb+{}]", back, frame));
+        self.emit(format!("arb {}", frame));
+        self.emit(format!("jt 1, {}", name));
+        self.place(&back);
+        self.emit(format!("arb -{}", frame));
+
+        self.next = frame + 1;
+        self.emit(format!("add [rb+{}], 0, [rb+{}]", frame + 1, frame));
+        Ok(Value::Slot(frame))
+    }
+}
+
+// An overflow is not folded and is left to fail at run time.
+fn unary(op: &str, value: i128) -> Option<i128> {
+    match op {
+        "-" => value.checked_neg(),
+        _ => Some((value == 0) as i128),
+    }
+}
+
+fn binary(op: &str, a: i128, b: i128) -> Option<i128> {
+    Some(match op {
+        "+" => a.checked_add(b)?,
+        "-" => a.checked_sub(b)?,
+        "*" => a.checked_mul(b)?,
+        "==" => (a == b) as i128,
+        "!=" => (a != b) as i128,
+        "<" => (a < b) as i128,
+        ">" => (a > b) as i128,
+        "<=" => (a <= b) as i128,
+        ">=" => (a >= b) as i128,
+        _ => return None,
+    })
+}
+
+// Compiles a small language to assembly for `assemble`:
+//
+//     fn main() {
+//         let n = input();
+//         while n > 0 { output(square(n)); n = n - 1; }
+//     }
+//     fn square(x) { return x * x; }
+//
+// Values are integers, `0` is false. There are `let`, assignment,
+// `if`/`else`, `while`, `return`, calls, `input()` and `output(value)`.
+// The stack starts right behind the program.
+pub fn compile(source: &str) -> MachineResult<String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+
+    let mut functions = Vec::new();
+    while parser.peek().is_some() {
+        functions.push(parser.function()?);
+    }
+
+    let mut signatures = HashMap::new();
+    for function in &functions {
+        if ["input", "output"].contains(&function.name.as_str())
+            || signatures
+                .insert(function.name.clone(), function.params.len())
+                .is_some()
+        {
+            return Err(compile_error(
+                function.line,
+                format!("Function '{}' is already defined.", function.name),
+            ));
+        }
+    }
+    if signatures.get("main") != Some(&0) {
+        return Err(compile_error(
+            1,
+            "Missing 'fn main()' without parameters.".to_owned(),
+        ));
+    }
+
+    let mut generator = Generator {
+        functions: &signatures,
+        source: source.lines().collect(),
+        output: String::new(),
+        labels: 0,
+        scopes: Vec::new(),
+        next: 0,
+    };
+
+    generator.emit("arb .stack".to_owned());
+    generator.emit("add .exit, 0, [rb+0]".to_owned());
+    generator.emit("jt 1, main".to_owned());
+    generator.place(".exit");
+    generator.emit("hlt".to_owned());
+
+    for function in &functions {
+        generator.function(function)?;
+    }
+    generator.place("\n.stack");
+
+    Ok(generator.output)
+}
+
+pub fn compile_program(source: &str) -> MachineResult<Assembly> {
+    assemble(&compile(source)?)
+}
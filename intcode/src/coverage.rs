@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use super::{decode, format_line, Executer, Observer, OpCode, RunOutcome};
+
+#[derive(Debug, Default)]
+struct CoverageData {
+    // Address of an executed instruction -> how often it ran
+    instructions: BTreeMap<i128, u64>,
+    code: BTreeSet<i128>,
+    read: BTreeSet<i128>,
+    written: BTreeSet<i128>,
+    // Address of a conditional jump -> (taken, not taken)
+    branches: BTreeMap<i128, (bool, bool)>,
+    pending_branch: Option<i128>,
+}
+
+impl CoverageData {
+    fn resolve_branch(&mut self, counter: Option<i128>) {
+        if let Some(address) = self.pending_branch.take() {
+            let entry = self.branches.entry(address).or_default();
+            if counter == Some(address + 3) {
+                entry.1 = true;
+            } else {
+                entry.0 = true;
+            }
+        }
+    }
+}
+
+// Records which addresses a run executed as instructions and which it read
+// or wrote as data. Clones share their data, so one clone can be attached
+// to several executers to merge their coverage.
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    data: Arc<Mutex<CoverageData>>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage::default()
+    }
+
+    pub fn report(&self, program: &[i128]) -> CoverageReport {
+        let data = self.data.lock().unwrap();
+        let size = program.len() as i128;
+        let inside = |set: &BTreeSet<i128>| set.range(0..size).count();
+
+        let data_words: BTreeSet<i128> = data.read.union(&data.written).copied().collect();
+        let touched: BTreeSet<i128> = data_words.union(&data.code).copied().collect();
+
+        CoverageReport {
+            size: program.len(),
+            code: inside(&data.code),
+            data: inside(&data_words),
+            untouched: program.len() - inside(&touched),
+            branches: data.branches.clone(),
+        }
+    }
+
+    // How often each executed instruction ran, by address.
+    pub fn hits(&self) -> BTreeMap<i128, u64> {
+        self.data.lock().unwrap().instructions.clone()
+    }
+
+    // Listing of the program, where every line is marked with how the run
+    // used it: `X` executed, `R` read, `W` written, `.` untouched, and
+    // instructions with how often they ran. Only executed instructions are
+    // decoded, everything else is shown as data.
+    pub fn annotate(&self, program: &[i128]) -> String {
+        let data = self.data.lock().unwrap();
+        let mut lines = Vec::new();
+        let mut address = 0;
+
+        while address < program.len() as i128 {
+            let mark = |set: &BTreeSet<i128>, c: char| if set.contains(&address) { c } else { ' ' };
+            let mut marker: String = [
+                mark(&data.code, 'X'),
+                mark(&data.read, 'R'),
+                mark(&data.written, 'W'),
+            ]
+            .iter()
+            .collect();
+            if marker.trim().is_empty() {
+                marker = " . ".to_owned();
+            }
+
+            let hits = data.instructions.get(&address).copied();
+            let instruction = hits.and_then(|_| decode(program, address));
+            marker = match hits {
+                Some(hits) => format!("{} {:>8}", marker, hits),
+                None => format!("{} {:>8}", marker, ""),
+            };
+
+            match instruction {
+                Some(instruction) => {
+                    let mut text = instruction.to_string();
+                    match data.branches.get(&address) {
+                        Some((true, true)) | None => {}
+                        Some((true, false)) => text.push_str("    ; always taken"),
+                        Some((false, _)) => text.push_str("    ; never taken"),
+                    }
+
+                    lines.push(format_line(address, &marker, &text));
+                    address += instruction.length();
+                }
+                None => {
+                    let text = format!("data {}", program[address as usize]);
+                    lines.push(format_line(address, &marker, &text));
+                    address += 1;
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl Observer for Coverage {
+    fn instruction(&mut self, executer: &Executer, code: &OpCode) {
+        let mut data = self.data.lock().unwrap();
+        let counter = executer.counter();
+
+        data.resolve_branch(Some(counter));
+        *data.instructions.entry(counter).or_default() += 1;
+        for index in counter..counter.saturating_add(code.length().unwrap_or(1)) {
+            data.code.insert(index);
+        }
+
+        if code.op() == 5 || code.op() == 6 {
+            data.pending_branch = Some(counter);
+        }
+    }
+
+    fn read(&mut self, _executer: &Executer, address: i128, _value: i128) {
+        self.data.lock().unwrap().read.insert(address);
+    }
+
+    fn write(&mut self, _executer: &Executer, address: i128, _value: i128) {
+        self.data.lock().unwrap().written.insert(address);
+    }
+
+    fn halt(&mut self, _executer: &Executer, _outcome: &RunOutcome) {
+        self.data.lock().unwrap().resolve_branch(None);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CoverageReport {
+    pub size: usize,
+    pub code: usize,
+    pub data: usize,
+    pub untouched: usize,
+    // Address of a conditional jump -> (taken, not taken)
+    pub branches: BTreeMap<i128, (bool, bool)>,
+}
+
+impl std::fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let percent = |count: usize| {
+            if self.size == 0 {
+                0.0
+            } else {
+                count as f64 * 100.0 / self.size as f64
+            }
+        };
+
+        writeln!(
+            f,
+            "Code:      {:>6} / {} ({:.1}%)",
+            self.code,
+            self.size,
+            percent(self.code)
+        )?;
+        writeln!(
+            f,
+            "Data:      {:>6} / {} ({:.1}%)",
+            self.data,
+            self.size,
+            percent(self.data)
+        )?;
+        writeln!(
+            f,
+            "Untouched: {:>6} / {} ({:.1}%)",
+            self.untouched,
+            self.size,
+            percent(self.untouched)
+        )?;
+
+        let directions: usize = self
+            .branches
+            .values()
+            .map(|(taken, not_taken)| *taken as usize + *not_taken as usize)
+            .sum();
+        write!(
+            f,
+            "Branches:  {:>6} / {} directions",
+            directions,
+            self.branches.len() * 2
+        )
+    }
+}
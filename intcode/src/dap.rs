@@ -0,0 +1,727 @@
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+
+use super::{
+    assemble, decode, evaluate_expression, parse_file, Assembly, Condition, Debugger, Executer,
+    Frame, Json, MachineError, MachineResult, PauseRequest, QueueInterface, RunOutcome, StopReason,
+};
+
+const THREAD: i128 = 1;
+const REGISTERS: i128 = 1;
+const STACK: i128 = 2;
+const MEMORY: i128 = 3;
+// Words shown in the stack scope, starting at the relative base.
+const STACK_WORDS: i128 = 16;
+// Larger messages are refused before their body is read.
+const MAX_MESSAGE: usize = 1 << 20;
+// Instructions returned by one disassemble request at most.
+const MAX_INSTRUCTIONS: i128 = 10_000;
+
+fn dap_error(reason: String) -> MachineError {
+    MachineError {
+        message: "Debug adapter error!".to_owned(),
+        reason,
+    }
+}
+
+// Reads one message with its `Content-Length` header, `None` at the end of
+// the stream. A body that is not JSON is returned as the inner error, the
+// stream can still be read after it.
+fn read_message<R: BufRead>(reader: &mut R) -> MachineResult<Option<MachineResult<Json>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+        } else if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = length.unwrap_or(0);
+    if length > MAX_MESSAGE {
+        return Err(dap_error(format!(
+            "A message of {} bytes is bigger than {} bytes.",
+            length, MAX_MESSAGE
+        )));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Json::parse(&String::from_utf8_lossy(&body))))
+}
+
+struct Session {
+    debugger: Debugger,
+    interface: QueueInterface,
+    // Set for programs loaded from an assembler listing.
+    assembly: Option<Assembly>,
+    path: String,
+    size: i128,
+    ascii: bool,
+    stop_on_entry: bool,
+    source_breakpoints: BTreeSet<i128>,
+    instruction_breakpoints: BTreeSet<i128>,
+}
+
+impl Session {
+    fn function_name(&self, address: i128) -> String {
+        self.assembly
+            .as_ref()
+            .and_then(|assembly| {
+                assembly
+                    .labels
+                    .iter()
+                    .find(|(name, a)| **a == address && !name.starts_with('.'))
+            })
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| format!("@{}", address))
+    }
+
+    fn update_breakpoints(&mut self, old: &BTreeSet<i128>) {
+        for address in old {
+            self.debugger.remove_breakpoint(*address);
+        }
+        for address in self
+            .source_breakpoints
+            .iter()
+            .chain(self.instruction_breakpoints.iter())
+        {
+            self.debugger.add_breakpoint(*address);
+        }
+    }
+}
+
+// `"text"` is sent as ASCII followed by a newline, anything else as comma
+// separated values.
+fn parse_input(text: &str) -> Vec<i128> {
+    let text = text.trim();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        let mut values: Vec<i128> = text[1..text.len() - 1].chars().map(|c| c as i128).collect();
+        values.push(10);
+        return values;
+    }
+    super::parse(text)
+}
+
+// Serves the Debug Adapter Protocol. Requests are read on their own thread,
+// so a `pause` can interrupt a running program.
+//
+// `launch` takes `program`, the path to a comma separated program or to an
+// assembler listing ending in `.asm`; breakpoints can be set by line only
+// for the latter. `input` is an array of values or a string sent as ASCII,
+// `ascii` shows the output as text and `stopOnEntry` pauses in front of the
+// first instruction. More input is sent by evaluating `input 1, 2` or
+// `input "text"`, other expressions are conditions or operands as the
+// debugger understands them. A message that is not JSON gets an error
+// response without a command.
+pub fn serve_dap<R, W>(mut reader: R, writer: W) -> MachineResult<()>
+where
+    R: BufRead + Send + 'static,
+    W: Write,
+{
+    let pause = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+
+    let requested = pause.clone();
+    std::thread::spawn(move || {
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if let Ok(message) = &message {
+                if message.get("command").as_str() == Some("pause") {
+                    requested.store(true, Ordering::Relaxed);
+                }
+            }
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut server = DapServer {
+        writer,
+        seq: 0,
+        session: None,
+        pause,
+    };
+
+    for message in receiver {
+        match message {
+            Ok(message) => {
+                if !server.handle(&message)? {
+                    break;
+                }
+            }
+            // There is no request to refer to, so the response has none.
+            Err(error) => {
+                let request =
+                    Json::object(vec![("seq", Json::from(0)), ("command", Json::from(""))]);
+                server.respond(&request, Err(error))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn serve_dap_stdio() -> MachineResult<()> {
+    serve_dap(std::io::BufReader::new(std::io::stdin()), std::io::stdout())
+}
+
+struct DapServer<W: Write> {
+    writer: W,
+    seq: i128,
+    session: Option<Session>,
+    pause: Arc<AtomicBool>,
+}
+
+impl<W: Write> DapServer<W> {
+    fn send(&mut self, mut fields: Vec<(&str, Json)>) -> MachineResult<()> {
+        self.seq += 1;
+        fields.insert(0, ("seq", Json::from(self.seq)));
+
+        let text = Json::object(fields).to_string();
+        write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{}",
+            text.len(),
+            text
+        )?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> MachineResult<()> {
+        self.send(vec![
+            ("type", Json::from("event")),
+            ("event", Json::from(event)),
+            ("body", body),
+        ])
+    }
+
+    fn respond(&mut self, request: &Json, result: MachineResult<Json>) -> MachineResult<()> {
+        let mut fields = vec![
+            ("type", Json::from("response")),
+            ("request_seq", request.get("seq").clone()),
+            ("command", request.get("command").clone()),
+        ];
+        match result {
+            Ok(body) => {
+                fields.push(("success", Json::from(true)));
+                fields.push(("body", body));
+            }
+            Err(error) => {
+                fields.push(("success", Json::from(false)));
+                fields.push((
+                    "message",
+                    Json::from(format!("{} {}", error.message, error.reason)),
+                ));
+            }
+        }
+        self.send(fields)
+    }
+
+    fn session(&mut self) -> MachineResult<&mut Session> {
+        self.session
+            .as_mut()
+            .ok_or_else(|| dap_error("No program is loaded.".to_owned()))
+    }
+
+    // Returns false once the client disconnects.
+    fn handle(&mut self, request: &Json) -> MachineResult<bool> {
+        if request.get("type").as_str() != Some("request") {
+            return Ok(true);
+        }
+        let command = request.get("command").as_str().unwrap_or("").to_owned();
+        let arguments = request.get("arguments");
+
+        match command.as_str() {
+            "initialize" => {
+                let capabilities = Json::object(vec![
+                    ("supportsConfigurationDoneRequest", Json::from(true)),
+                    ("supportsInstructionBreakpoints", Json::from(true)),
+                    ("supportsDisassembleRequest", Json::from(true)),
+                    ("supportsEvaluateForHovers", Json::from(true)),
+                ]);
+                self.respond(request, Ok(capabilities))?;
+                self.event("initialized", Json::object(vec![]))?;
+            }
+            "launch" => {
+                let result = self.launch(arguments);
+                self.respond(request, result)?;
+            }
+            "configurationDone" => {
+                let stop_on_entry = self.session().map(|s| s.stop_on_entry);
+                self.respond(request, stop_on_entry.clone().map(|_| Json::Null))?;
+                // The raised pause flag stops the run in front of the first
+                // instruction, so a breakpoint there is reported as such.
+                if let Ok(stop_on_entry) = stop_on_entry {
+                    self.pause.store(stop_on_entry, Ordering::Relaxed);
+                    self.run_reporting("entry", |session| session.debugger.resume())?;
+                }
+            }
+            "continue" => {
+                self.respond(
+                    request,
+                    Ok(Json::object(vec![(
+                        "allThreadsContinued",
+                        Json::from(true),
+                    )])),
+                )?;
+                self.run(|session| session.debugger.resume())?;
+            }
+            "next" => {
+                self.respond(request, Ok(Json::Null))?;
+                self.run(step_over)?;
+            }
+            "stepIn" => {
+                self.respond(request, Ok(Json::Null))?;
+                self.run(|session| session.debugger.step())?;
+            }
+            "stepOut" => {
+                self.respond(request, Ok(Json::Null))?;
+                self.run(step_out)?;
+            }
+            "pause" => {
+                // Still raised means the program had stopped anyway.
+                self.pause.store(false, Ordering::Relaxed);
+                self.respond(request, Ok(Json::Null))?;
+            }
+            "disconnect" | "terminate" => {
+                self.respond(request, Ok(Json::Null))?;
+                return Ok(false);
+            }
+            _ => {
+                let result = self.query(&command, arguments);
+                self.respond(request, result)?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn launch(&mut self, arguments: &Json) -> MachineResult<Json> {
+        let path = arguments
+            .get("program")
+            .as_str()
+            .ok_or_else(|| dap_error("Missing 'program'.".to_owned()))?
+            .to_owned();
+
+        let assembly = if path.ends_with(".asm") {
+            Some(assemble(&std::fs::read_to_string(&path)?)?)
+        } else {
+            None
+        };
+        let program = match &assembly {
+            Some(assembly) => assembly.program.clone(),
+            None => parse_file(&path)?,
+        };
+
+        let interface = QueueInterface::new();
+        let mut executer = Executer::new(0, &program, Box::new(interface.clone()), false);
+        executer.add_observer(Box::new(PauseRequest::new(self.pause.clone())));
+
+        let session = Session {
+            debugger: Debugger::new(executer),
+            interface,
+            assembly,
+            path,
+            size: program.len() as i128,
+            ascii: arguments.get("ascii").as_bool().unwrap_or(false),
+            stop_on_entry: arguments.get("stopOnEntry").as_bool().unwrap_or(false),
+            source_breakpoints: BTreeSet::new(),
+            instruction_breakpoints: BTreeSet::new(),
+        };
+
+        // A string is sent as is, so it should contain its own newlines.
+        let values: Vec<i128> = match arguments.get("input") {
+            Json::String(text) => text.chars().map(|c| c as i128).collect(),
+            Json::Array(values) => values.iter().filter_map(|v| v.as_i128()).collect(),
+            _ => Vec::new(),
+        };
+        session.interface.push_input(&values);
+
+        self.session = Some(session);
+        Ok(Json::Null)
+    }
+
+    // Requests that only read or configure the session.
+    fn query(&mut self, command: &str, arguments: &Json) -> MachineResult<Json> {
+        let session = self.session()?;
+
+        match command {
+            "setBreakpoints" => {
+                let old = session.source_breakpoints.clone();
+                session.source_breakpoints.clear();
+
+                let mut breakpoints = Vec::new();
+                for breakpoint in arguments.get("breakpoints").as_array().unwrap_or(&[]) {
+                    let line = breakpoint.get("line").as_i128().unwrap_or(0) as usize;
+                    // Lines without code break at the next one that has some.
+                    let target = session.assembly.as_ref().and_then(|assembly| {
+                        assembly
+                            .lines
+                            .iter()
+                            .filter(|(_, l)| **l >= line)
+                            .min_by_key(|(_, l)| **l)
+                            .map(|(address, l)| (*address, *l))
+                    });
+
+                    breakpoints.push(match target {
+                        Some((address, line)) => {
+                            session.source_breakpoints.insert(address);
+                            Json::object(vec![
+                                ("verified", Json::from(true)),
+                                ("line", Json::from(line as i128)),
+                                ("instructionReference", Json::from(address.to_string())),
+                            ])
+                        }
+                        None => Json::object(vec![("verified", Json::from(false))]),
+                    });
+                }
+
+                session.update_breakpoints(&old);
+                Ok(Json::object(vec![("breakpoints", Json::from(breakpoints))]))
+            }
+            "setInstructionBreakpoints" => {
+                let old = session.instruction_breakpoints.clone();
+                session.instruction_breakpoints.clear();
+
+                let mut breakpoints = Vec::new();
+                for breakpoint in arguments.get("breakpoints").as_array().unwrap_or(&[]) {
+                    let reference = breakpoint.get("instructionReference").as_str();
+                    let offset = breakpoint.get("offset").as_i128().unwrap_or(0);
+                    match reference
+                        .and_then(|r| r.parse::<i128>().ok())
+                        .and_then(|address| address.checked_add(offset))
+                    {
+                        Some(address) => {
+                            session.instruction_breakpoints.insert(address);
+                            breakpoints.push(Json::object(vec![
+                                ("verified", Json::from(true)),
+                                ("instructionReference", Json::from(address.to_string())),
+                            ]));
+                        }
+                        None => {
+                            breakpoints.push(Json::object(vec![("verified", Json::from(false))]))
+                        }
+                    }
+                }
+
+                session.update_breakpoints(&old);
+                Ok(Json::object(vec![("breakpoints", Json::from(breakpoints))]))
+            }
+            "threads" => Ok(Json::object(vec![(
+                "threads",
+                Json::from(vec![Json::object(vec![
+                    ("id", Json::from(THREAD)),
+                    ("name", Json::from("intcode")),
+                ])]),
+            )])),
+            "stackTrace" => Ok(stack_trace(session)),
+            "scopes" => {
+                let scope = |name: &str, reference: i128, indexed: Option<i128>| {
+                    let mut fields = vec![
+                        ("name", Json::from(name)),
+                        ("variablesReference", Json::from(reference)),
+                        ("expensive", Json::from(indexed.is_some())),
+                    ];
+                    if let Some(count) = indexed {
+                        fields.push(("indexedVariables", Json::from(count)));
+                    }
+                    Json::object(fields)
+                };
+
+                Ok(Json::object(vec![(
+                    "scopes",
+                    Json::from(vec![
+                        scope("Registers", REGISTERS, None),
+                        scope("Stack", STACK, None),
+                        scope("Memory", MEMORY, Some(session.size)),
+                    ]),
+                )]))
+            }
+            "variables" => Ok(variables(session, arguments)),
+            "evaluate" => {
+                let expression = arguments.get("expression").as_str().unwrap_or("").trim();
+                let result = if let Some(text) = expression.strip_prefix("input ") {
+                    session.interface.push_input(&parse_input(text));
+                    format!("{} values waiting", session.interface.pending_input())
+                } else {
+                    evaluate_expression(expression, session.debugger.executer())?.to_string()
+                };
+
+                Ok(Json::object(vec![
+                    ("result", Json::from(result)),
+                    ("variablesReference", Json::from(0)),
+                ]))
+            }
+            "disassemble" => Ok(disassemble_request(session, arguments)),
+            _ => Err(dap_error(format!("Unsupported request '{}'.", command))),
+        }
+    }
+
+    fn run<F>(&mut self, run: F) -> MachineResult<()>
+    where
+        F: FnOnce(&mut Session) -> RunOutcome,
+    {
+        self.run_reporting("pause", run)
+    }
+
+    // Runs the session, then reports the output and why it stopped.
+    // `paused` is the reason for a pause no breakpoint explains.
+    fn run_reporting<F>(&mut self, paused: &str, run: F) -> MachineResult<()>
+    where
+        F: FnOnce(&mut Session) -> RunOutcome,
+    {
+        let session = match self.session.as_mut() {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+        let outcome = run(session);
+
+        let output = session.interface.take_output();
+        let ascii = session.ascii;
+        let reason = session.debugger.reason();
+
+        if !output.is_empty() {
+            let text: String = output
+                .iter()
+                .map(|value| {
+                    if ascii && (0..128).contains(value) {
+                        (*value as u8 as char).to_string()
+                    } else {
+                        format!("{}\n", value)
+                    }
+                })
+                .collect();
+            self.event(
+                "output",
+                Json::object(vec![
+                    ("category", Json::from("stdout")),
+                    ("output", Json::from(text)),
+                ]),
+            )?;
+        }
+
+        match outcome {
+            RunOutcome::Halted => {
+                self.event("exited", Json::object(vec![("exitCode", Json::from(0))]))?;
+                self.event("terminated", Json::object(vec![]))
+            }
+            RunOutcome::Exhausted => self.stopped("pause", Some("Waiting for input".to_owned())),
+            RunOutcome::Failed(error) => self.stopped(
+                "exception",
+                Some(format!("{} {}", error.message, error.reason)),
+            ),
+            RunOutcome::Paused | RunOutcome::Cancelled => match reason {
+                Some(StopReason::Breakpoint(_)) => self.stopped("breakpoint", None),
+                Some(StopReason::Watch { .. }) => self.stopped("data breakpoint", None),
+                Some(StopReason::Condition(_)) | Some(StopReason::Step) => {
+                    self.stopped("step", None)
+                }
+                None => self.stopped(paused, None),
+            },
+        }
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<String>) -> MachineResult<()> {
+        let mut fields = vec![
+            ("reason", Json::from(reason)),
+            ("threadId", Json::from(THREAD)),
+            ("allThreadsStopped", Json::from(true)),
+        ];
+        if let Some(text) = text {
+            fields.push(("description", Json::from(text.clone())));
+            fields.push(("text", Json::from(text)));
+        }
+        self.event("stopped", Json::object(fields))
+    }
+}
+
+// Runs until `frame` returns to its caller. The relative base tells a
+// return from a recursive call at the same address apart.
+fn run_until_return(session: &mut Session, frame: Frame) -> RunOutcome {
+    let source = format!(
+        "counter == {} && relative == {}",
+        frame.return_address, frame.relative
+    );
+    let condition = match Condition::parse(&source) {
+        Ok(condition) => condition,
+        Err(error) => return RunOutcome::Failed(error),
+    };
+
+    session.debugger.add_condition(condition);
+    let outcome = session.debugger.resume();
+    session.debugger.remove_condition(&source);
+    outcome
+}
+
+fn step_over(session: &mut Session) -> RunOutcome {
+    let depth = session.debugger.call_stack().len();
+    let outcome = session.debugger.step();
+
+    let frames = session.debugger.call_stack();
+    match outcome {
+        RunOutcome::Paused
+            if session.debugger.reason() == Some(StopReason::Step) && frames.len() > depth =>
+        {
+            run_until_return(session, frames[depth])
+        }
+        outcome => outcome,
+    }
+}
+
+fn step_out(session: &mut Session) -> RunOutcome {
+    match session.debugger.call_stack().last() {
+        Some(frame) => run_until_return(session, *frame),
+        None => session.debugger.resume(),
+    }
+}
+
+fn stack_trace(session: &Session) -> Json {
+    let frames = session.debugger.call_stack();
+
+    // The current position first, then every call site out to the program
+    // itself.
+    let mut positions = vec![session.debugger.executer().counter()];
+    positions.extend(frames.iter().rev().map(|f| f.call_site));
+    let mut functions: Vec<i128> = frames.iter().rev().map(|f| f.function).collect();
+    functions.push(0);
+
+    let stack_frames: Vec<Json> = positions
+        .iter()
+        .zip(functions.iter())
+        .enumerate()
+        .map(|(id, (address, function))| {
+            let mut fields = vec![
+                ("id", Json::from(id as i128)),
+                ("name", Json::from(session.function_name(*function))),
+                (
+                    "instructionPointerReference",
+                    Json::from(address.to_string()),
+                ),
+                ("column", Json::from(1)),
+            ];
+            match session.assembly.as_ref().and_then(|a| a.line(*address)) {
+                Some(line) => {
+                    fields.push(("line", Json::from(line as i128)));
+                    fields.push((
+                        "source",
+                        Json::object(vec![("path", Json::from(session.path.clone()))]),
+                    ));
+                }
+                None => fields.push(("line", Json::from(0))),
+            }
+            Json::object(fields)
+        })
+        .collect();
+
+    Json::object(vec![
+        ("totalFrames", Json::from(stack_frames.len() as i128)),
+        ("stackFrames", Json::from(stack_frames)),
+    ])
+}
+
+fn variables(session: &Session, arguments: &Json) -> Json {
+    let executer = session.debugger.executer();
+    let variable = |name: String, value: i128| {
+        Json::object(vec![
+            ("name", Json::from(name)),
+            ("value", Json::from(value.to_string())),
+            ("variablesReference", Json::from(0)),
+        ])
+    };
+
+    let variables = match arguments.get("variablesReference").as_i128() {
+        Some(REGISTERS) => vec![
+            variable("counter".to_owned(), executer.counter()),
+            variable("relative".to_owned(), executer.relative()),
+        ],
+        Some(STACK) => (0..STACK_WORDS)
+            .map(|offset| {
+                let value = executer
+                    .relative()
+                    .checked_add(offset)
+                    .and_then(|address| executer.get(address).ok())
+                    .unwrap_or(0);
+                variable(format!("[rb+{}]", offset), value)
+            })
+            .collect(),
+        Some(MEMORY) => {
+            let start = arguments.get("start").as_i128().unwrap_or(0).max(0);
+            let count = arguments.get("count").as_i128().unwrap_or(session.size);
+            (start..start.saturating_add(count).min(session.size))
+                .map(|address| {
+                    variable(format!("[{}]", address), executer.get(address).unwrap_or(0))
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    Json::object(vec![("variables", Json::from(variables))])
+}
+
+// Decodes linearly from the referenced address, words that do not decode
+// are shown as data.
+fn disassemble_request(session: &Session, arguments: &Json) -> Json {
+    let executer = session.debugger.executer();
+    let reference = arguments
+        .get("memoryReference")
+        .as_str()
+        .and_then(|r| r.parse::<i128>().ok())
+        .unwrap_or(0);
+    let mut next = reference
+        .checked_add(arguments.get("offset").as_i128().unwrap_or(0))
+        .and_then(|a| a.checked_add(arguments.get("instructionOffset").as_i128().unwrap_or(0)));
+    let count = arguments
+        .get("instructionCount")
+        .as_i128()
+        .unwrap_or(0)
+        .min(MAX_INSTRUCTIONS);
+
+    let mut instructions = Vec::new();
+    for _ in 0..count {
+        // Stops at the end of the address space.
+        let address = match next {
+            Some(address) => address,
+            None => break,
+        };
+        let words: Vec<i128> = (0..4)
+            .map(|index| {
+                address
+                    .checked_add(index)
+                    .and_then(|a| executer.get(a).ok())
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let (text, length) = match decode(&words, 0) {
+            Some(instruction) if address >= 0 => (instruction.to_string(), instruction.length()),
+            _ => (format!("data {}", words[0]), 1),
+        };
+
+        let mut fields = vec![
+            ("address", Json::from(address.to_string())),
+            ("instruction", Json::from(text)),
+        ];
+        if let Some(line) = session.assembly.as_ref().and_then(|a| a.line(address)) {
+            fields.push(("line", Json::from(line as i128)));
+            fields.push((
+                "location",
+                Json::object(vec![("path", Json::from(session.path.clone()))]),
+            ));
+        }
+        instructions.push(Json::object(fields));
+        next = address.checked_add(length);
+    }
+
+    Json::object(vec![("instructions", Json::from(instructions))])
+}
@@ -0,0 +1,482 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use super::{CallStack, Executer, Frame, MachineError, MachineResult, Observer, RunOutcome};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watch {
+    Read(i128),
+    Write(i128),
+    Access(i128),
+    // Pauses when `value` is written to the address.
+    WriteValue(i128, i128),
+}
+
+impl Watch {
+    fn matches(&self, address: i128, value: i128, write: bool) -> bool {
+        match *self {
+            Watch::Read(a) => !write && a == address,
+            Watch::Write(a) => write && a == address,
+            Watch::Access(a) => a == address,
+            Watch::WriteValue(a, v) => write && a == address && v == value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Counter,
+    Relative,
+    Literal(i128),
+    Memory(Box<Operand>),
+    Add(Box<Operand>, Box<Operand>),
+    Sub(Box<Operand>, Box<Operand>),
+}
+
+impl Operand {
+    fn evaluate(&self, executer: &Executer) -> MachineResult<i128> {
+        let result = match self {
+            Operand::Counter => Some(executer.counter()),
+            Operand::Relative => Some(executer.relative()),
+            Operand::Literal(value) => Some(*value),
+            Operand::Memory(address) => {
+                Some(executer.get(address.evaluate(executer)?).unwrap_or(0))
+            }
+            Operand::Add(a, b) => a.evaluate(executer)?.checked_add(b.evaluate(executer)?),
+            Operand::Sub(a, b) => a.evaluate(executer)?.checked_sub(b.evaluate(executer)?),
+        };
+        result.ok_or_else(|| MachineError {
+            message: "Arithmetic overflow!".to_owned(),
+            reason: "The expression leaves the range of an i128.".to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expression {
+    Compare(Operand, String, Operand),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    fn evaluate(&self, executer: &Executer) -> MachineResult<bool> {
+        Ok(match self {
+            Expression::Compare(a, op, b) => {
+                let (a, b) = (a.evaluate(executer)?, b.evaluate(executer)?);
+                match op.as_str() {
+                    "==" => a == b,
+                    "!=" => a != b,
+                    "<" => a < b,
+                    "<=" => a <= b,
+                    ">" => a > b,
+                    _ => a >= b,
+                }
+            }
+            Expression::And(a, b) => a.evaluate(executer)? && b.evaluate(executer)?,
+            Expression::Or(a, b) => a.evaluate(executer)? || b.evaluate(executer)?,
+        })
+    }
+}
+
+// A condition on the executer state, for example
+// `counter == 12 && [relative + 1] != 0`. Operands are `counter`,
+// `relative`, integers and `[...]` for the memory cell at an address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    source: String,
+    expression: Expression,
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> MachineResult<Self> {
+        let mut parser = ConditionParser {
+            tokens: tokenize(source)?,
+            position: 0,
+        };
+
+        let expression = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(condition_error(format!("Unexpected '{}'.", token)));
+        }
+
+        Ok(Condition {
+            source: source.trim().to_owned(),
+            expression,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn evaluate(&self, executer: &Executer) -> MachineResult<bool> {
+        self.expression.evaluate(executer)
+    }
+}
+
+// Evaluates a single operand such as `[relative + 1]`, or a condition,
+// which gives 1 or 0.
+pub fn evaluate_expression(source: &str, executer: &Executer) -> MachineResult<i128> {
+    let mut parser = ConditionParser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+
+    if let Ok(operand) = parser.operand() {
+        if parser.peek().is_none() {
+            return operand.evaluate(executer);
+        }
+    }
+
+    Ok(Condition::parse(source)?.evaluate(executer)? as i128)
+}
+
+fn condition_error(reason: String) -> MachineError {
+    MachineError {
+        message: "Illegal condition!".to_owned(),
+        reason,
+    }
+}
+
+fn tokenize(source: &str) -> MachineResult<Vec<String>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+        let next = chars.get(index + 1).copied().unwrap_or(' ');
+
+        if c.is_whitespace() {
+            index += 1;
+        } else if c.is_ascii_alphanumeric() {
+            let start = index;
+            while index < chars.len() && chars[index].is_ascii_alphanumeric() {
+                index += 1;
+            }
+            tokens.push(chars[start..index].iter().collect());
+        } else if ["==", "!=", "<=", ">=", "&&", "||"].contains(&format!("{}{}", c, next).as_str())
+        {
+            tokens.push(format!("{}{}", c, next));
+            index += 2;
+        } else if "<>[]+-".contains(c) {
+            tokens.push(c.to_string());
+            index += 1;
+        } else {
+            return Err(condition_error(format!("Unexpected character '{}'.", c)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct ConditionParser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl ConditionParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.as_str())
+    }
+
+    fn next(&mut self) -> MachineResult<String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| condition_error("Unexpected end of condition.".to_owned()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn or(&mut self) -> MachineResult<Expression> {
+        let mut expression = self.and()?;
+        while self.peek() == Some("||") {
+            self.position += 1;
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> MachineResult<Expression> {
+        let mut expression = self.compare()?;
+        while self.peek() == Some("&&") {
+            self.position += 1;
+            expression = Expression::And(Box::new(expression), Box::new(self.compare()?));
+        }
+        Ok(expression)
+    }
+
+    fn compare(&mut self) -> MachineResult<Expression> {
+        let left = self.operand()?;
+        let op = self.next()?;
+        if !["==", "!=", "<", "<=", ">", ">="].contains(&op.as_str()) {
+            return Err(condition_error(format!(
+                "Expected a comparison, found '{}'.",
+                op
+            )));
+        }
+        let right = self.operand()?;
+
+        Ok(Expression::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> MachineResult<Operand> {
+        let mut operand = self.term()?;
+        loop {
+            match self.peek() {
+                Some("+") => {
+                    self.position += 1;
+                    operand = Operand::Add(Box::new(operand), Box::new(self.term()?));
+                }
+                Some("-") => {
+                    self.position += 1;
+                    operand = Operand::Sub(Box::new(operand), Box::new(self.term()?));
+                }
+                _ => return Ok(operand),
+            }
+        }
+    }
+
+    fn term(&mut self) -> MachineResult<Operand> {
+        let token = self.next()?;
+        match token.as_str() {
+            "counter" => Ok(Operand::Counter),
+            "relative" => Ok(Operand::Relative),
+            "-" => Ok(Operand::Sub(
+                Box::new(Operand::Literal(0)),
+                Box::new(self.term()?),
+            )),
+            "[" => {
+                let address = self.operand()?;
+                match self.next()?.as_str() {
+                    "]" => Ok(Operand::Memory(Box::new(address))),
+                    other => Err(condition_error(format!("Expected ']', found '{}'.", other))),
+                }
+            }
+            _ => token
+                .parse::<i128>()
+                .map(Operand::Literal)
+                .map_err(|_| condition_error(format!("Unknown operand '{}'.", token))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    Breakpoint(i128),
+    // `counter` is the instruction that accessed the cell.
+    Watch {
+        watch: Watch,
+        counter: i128,
+        address: i128,
+        value: i128,
+    },
+    Condition(String),
+    Step,
+}
+
+#[derive(Debug, Default)]
+struct Breakpoints {
+    addresses: BTreeSet<i128>,
+    watches: Vec<Watch>,
+    conditions: Vec<Condition>,
+    pending: Option<StopReason>,
+    // Where a resumed run starts. Breakpoints and conditions there are
+    // skipped once, so the run does not stop right where it stopped before.
+    resume_from: Option<i128>,
+}
+
+impl Breakpoints {
+    fn access(&mut self, executer: &Executer, address: i128, value: i128, write: bool) {
+        if self.pending.is_some() {
+            return;
+        }
+
+        if let Some(watch) = self
+            .watches
+            .iter()
+            .find(|w| w.matches(address, value, write))
+        {
+            self.pending = Some(StopReason::Watch {
+                watch: *watch,
+                counter: executer.counter(),
+                address,
+                value,
+            });
+        }
+    }
+}
+
+struct BreakpointObserver {
+    breakpoints: Arc<Mutex<Breakpoints>>,
+    reason: Arc<Mutex<Option<StopReason>>>,
+}
+
+impl Observer for BreakpointObserver {
+    fn read(&mut self, executer: &Executer, address: i128, value: i128) {
+        let mut breakpoints = self.breakpoints.lock().unwrap();
+        breakpoints.access(executer, address, value, false);
+    }
+
+    fn write(&mut self, executer: &Executer, address: i128, value: i128) {
+        let mut breakpoints = self.breakpoints.lock().unwrap();
+        breakpoints.access(executer, address, value, true);
+    }
+
+    // Watches pause behind the accessing instruction, breakpoints and
+    // conditions in front of the instruction they refer to.
+    fn pause(&mut self, executer: &Executer) -> bool {
+        let mut breakpoints = self.breakpoints.lock().unwrap();
+
+        let resume_from = breakpoints.resume_from.take();
+
+        let reason = if let Some(reason) = breakpoints.pending.take() {
+            Some(reason)
+        } else if resume_from == Some(executer.counter()) {
+            None
+        } else if breakpoints.addresses.contains(&executer.counter()) {
+            Some(StopReason::Breakpoint(executer.counter()))
+        } else {
+            breakpoints
+                .conditions
+                .iter()
+                // A condition that cannot be evaluated is not met.
+                .find(|c| c.evaluate(executer).unwrap_or(false))
+                .map(|c| StopReason::Condition(c.source().to_owned()))
+        };
+
+        let pause = reason.is_some();
+        if pause {
+            *self.reason.lock().unwrap() = reason;
+        }
+        pause
+    }
+}
+
+pub struct Debugger {
+    executer: Executer,
+    breakpoints: Arc<Mutex<Breakpoints>>,
+    reason: Arc<Mutex<Option<StopReason>>>,
+    calls: CallStack,
+    started: bool,
+}
+
+impl Debugger {
+    pub fn new(mut executer: Executer) -> Self {
+        let breakpoints = Arc::new(Mutex::new(Breakpoints::default()));
+        let reason = Arc::new(Mutex::new(None));
+
+        let calls = CallStack::new();
+
+        executer.add_observer(Box::new(BreakpointObserver {
+            breakpoints: breakpoints.clone(),
+            reason: reason.clone(),
+        }));
+        executer.add_observer(Box::new(calls.clone()));
+
+        Debugger {
+            executer,
+            breakpoints,
+            reason,
+            calls,
+            started: false,
+        }
+    }
+
+    pub fn executer(&self) -> &Executer {
+        &self.executer
+    }
+
+    pub fn executer_mut(&mut self) -> &mut Executer {
+        &mut self.executer
+    }
+
+    // The inferred call stack, innermost frame last.
+    pub fn call_stack(&self) -> Vec<Frame> {
+        self.calls.frames()
+    }
+
+    pub fn calls(&self) -> &CallStack {
+        &self.calls
+    }
+
+    pub fn add_breakpoint(&mut self, address: i128) {
+        self.breakpoints.lock().unwrap().addresses.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: i128) {
+        self.breakpoints.lock().unwrap().addresses.remove(&address);
+    }
+
+    pub fn add_watch(&mut self, watch: Watch) {
+        self.breakpoints.lock().unwrap().watches.push(watch);
+    }
+
+    pub fn remove_watch(&mut self, watch: Watch) {
+        self.breakpoints
+            .lock()
+            .unwrap()
+            .watches
+            .retain(|w| *w != watch);
+    }
+
+    pub fn add_condition(&mut self, condition: Condition) {
+        self.breakpoints.lock().unwrap().conditions.push(condition);
+    }
+
+    pub fn remove_condition(&mut self, source: &str) {
+        self.breakpoints
+            .lock()
+            .unwrap()
+            .conditions
+            .retain(|c| c.source() != source);
+    }
+
+    pub fn clear(&mut self) {
+        *self.breakpoints.lock().unwrap() = Breakpoints::default();
+    }
+
+    pub fn breakpoints(&self) -> Vec<i128> {
+        let breakpoints = self.breakpoints.lock().unwrap();
+        breakpoints.addresses.iter().copied().collect()
+    }
+
+    fn skip_current(&mut self) {
+        self.breakpoints.lock().unwrap().resume_from = Some(self.executer.counter());
+    }
+
+    // Why the last `resume` or `step` paused, `None` if it did not pause.
+    pub fn reason(&self) -> Option<StopReason> {
+        self.reason.lock().unwrap().clone()
+    }
+
+    // The first run stops at a breakpoint on the entry instruction, later
+    // ones continue past the breakpoint they start from.
+    pub fn resume(&mut self) -> RunOutcome {
+        *self.reason.lock().unwrap() = None;
+        if self.started {
+            self.skip_current();
+        }
+        self.started = true;
+        self.executer.execute()
+    }
+
+    // Always executes the current instruction, even on a breakpoint.
+    pub fn step(&mut self) -> RunOutcome {
+        *self.reason.lock().unwrap() = None;
+        self.skip_current();
+        self.started = true;
+        let outcome = self.executer.step();
+
+        if let RunOutcome::Paused = outcome {
+            self.calls.update(&self.executer);
+            let mut reason = self.reason.lock().unwrap();
+            let pending = self.breakpoints.lock().unwrap().pending.take();
+            *reason = Some(pending.unwrap_or(StopReason::Step));
+        }
+        outcome
+    }
+}
@@ -0,0 +1,222 @@
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::OpCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Param {
+    Position(i128),
+    Immediate(i128),
+    Relative(i128),
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Param::Position(address) => write!(f, "[{}]", address),
+            Param::Immediate(value) => write!(f, "{}", value),
+            Param::Relative(offset) if offset < 0 => write!(f, "[rb-{}]", -offset),
+            Param::Relative(offset) => write!(f, "[rb+{}]", offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: i128,
+    pub op: i128,
+    pub params: Vec<Param>,
+}
+
+impl Instruction {
+    pub fn length(&self) -> i128 {
+        self.params.len() as i128 + 1
+    }
+
+    pub fn encode(&self) -> Vec<i128> {
+        let mut words = vec![self.op];
+
+        for (index, param) in self.params.iter().enumerate() {
+            let (mode, value) = match *param {
+                Param::Position(value) => (0, value),
+                Param::Immediate(value) => (1, value),
+                Param::Relative(value) => (2, value),
+            };
+            words[0] += mode * 10_i128.pow(index as u32 + 2);
+            words.push(value);
+        }
+        words
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        mnemonic(self.op).unwrap_or("???")
+    }
+
+    // Index of the parameter the instruction writes to, if any.
+    pub fn target(&self) -> Option<usize> {
+        match self.op {
+            1 | 2 | 7 | 8 => Some(2),
+            3 => Some(0),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())?;
+
+        for (index, param) in self.params.iter().enumerate() {
+            let separator = if index == 0 { " " } else { ", " };
+            write!(f, "{}{}", separator, param)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn mnemonic(op: i128) -> Option<&'static str> {
+    match op {
+        1 => Some("add"),
+        2 => Some("mul"),
+        3 => Some("in"),
+        4 => Some("out"),
+        5 => Some("jt"),
+        6 => Some("jf"),
+        7 => Some("lt"),
+        8 => Some("eq"),
+        9 => Some("arb"),
+        99 => Some("hlt"),
+        _ => None,
+    }
+}
+
+// Decodes the instruction at `address`, `None` if the word there is not a
+// valid instruction or the instruction runs past the end of memory.
+pub fn decode(memory: &[i128], address: i128) -> Option<Instruction> {
+    if address < 0 || address >= memory.len() as i128 {
+        return None;
+    }
+
+    let code = OpCode::new(memory[address as usize]);
+    let length = code.length()?;
+
+    if code.code() < 0 || code.code() / 10_i128.pow(length as u32 + 1) != 0 {
+        return None;
+    }
+
+    let mut instruction = Instruction {
+        address,
+        op: code.op(),
+        params: Vec::new(),
+    };
+
+    for position in 1..length {
+        let value = *memory.get((address + position) as usize)?;
+        instruction.params.push(match code.mode(position) {
+            0 => Param::Position(value),
+            1 => Param::Immediate(value),
+            2 => Param::Relative(value),
+            _ => return None,
+        });
+    }
+
+    if let Some(target) = instruction.target() {
+        if let Param::Immediate(_) = instruction.params[target] {
+            return None;
+        }
+    }
+
+    Some(instruction)
+}
+
+// Addresses the instruction can continue at, as far as they are known
+// without running it: fall-through and jumps with an immediate target.
+pub fn successors(instruction: &Instruction) -> Vec<i128> {
+    let next = instruction.address + instruction.length();
+    let mut successors = Vec::new();
+
+    match (instruction.op, &instruction.params[..]) {
+        (99, _) => {}
+        (5, [Param::Immediate(c), target]) | (6, [Param::Immediate(c), target]) => {
+            if (*c != 0) != (instruction.op == 5) {
+                successors.push(next);
+            } else if let Param::Immediate(target) = target {
+                successors.push(*target);
+            }
+        }
+        (5, [_, target]) | (6, [_, target]) => {
+            if let Param::Immediate(target) = target {
+                successors.push(*target);
+            }
+            successors.push(next);
+        }
+        _ => successors.push(next),
+    }
+
+    successors
+}
+
+// Follows the control flow from `entries`: fall-through and jumps with an
+// immediate target. Targets that are only known at runtime, like return
+// addresses on the stack, are not found unless they are passed as entries.
+pub fn trace_code(program: &[i128], entries: &[i128]) -> BTreeMap<i128, Instruction> {
+    let mut code = BTreeMap::new();
+    let mut pending: Vec<i128> = entries.to_owned();
+
+    while let Some(address) = pending.pop() {
+        if code.contains_key(&address) {
+            continue;
+        }
+
+        let instruction = match decode(program, address) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+
+        pending.extend(successors(&instruction));
+        code.insert(address, instruction);
+    }
+
+    code
+}
+
+// Formats one listing line, `annotation` is placed between the address and
+// the instruction.
+pub fn format_line(address: i128, annotation: &str, text: &str) -> String {
+    if annotation.is_empty() {
+        format!("{:>6}  {}", address, text)
+    } else {
+        format!("{:>6}  {}  {}", address, annotation, text)
+    }
+}
+
+// Linear sweep: everything that decodes is shown as an instruction, all
+// other words as data.
+pub fn disassemble(program: &[i128]) -> String {
+    let mut lines = Vec::new();
+    let mut address = 0;
+
+    while address < program.len() as i128 {
+        match decode(program, address) {
+            Some(instruction) => {
+                lines.push(format_line(address, "", &instruction.to_string()));
+                address += instruction.length();
+            }
+            None => {
+                lines.push(format_line(
+                    address,
+                    "",
+                    &format!("data {}", program[address as usize]),
+                ));
+                address += 1;
+            }
+        }
+    }
+
+    lines.join("\n")
+}
@@ -1,9 +1,14 @@
-#[derive(Debug)]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::{borrow::ToOwned, format};
+
+#[derive(Debug, Clone)]
 pub struct MachineError {
     pub message: String,
     pub reason: String,
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for MachineError {
     fn from(error: std::io::Error) -> Self {
         MachineError {
@@ -13,6 +18,7 @@ impl From<std::io::Error> for MachineError {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<std::sync::mpsc::RecvError> for MachineError {
     fn from(error: std::sync::mpsc::RecvError) -> Self {
         MachineError {
@@ -22,6 +28,7 @@ impl From<std::sync::mpsc::RecvError> for MachineError {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<std::sync::mpsc::SendError<i128>> for MachineError {
     fn from(error: std::sync::mpsc::SendError<i128>) -> Self {
         MachineError {
@@ -31,5 +38,4 @@ impl From<std::sync::mpsc::SendError<i128>> for MachineError {
     }
 }
 
-#[must_use]
 pub type MachineResult<T> = Result<T, MachineError>;
@@ -0,0 +1,500 @@
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::{MachineError, MachineInterface, MachineResult, Observer, OpCode};
+
+#[derive(Debug, Clone)]
+pub enum RunOutcome {
+    Halted,
+    // The interface signalled that no more input will arrive.
+    Exhausted,
+    // An observer asked to pause, or a single step is done.
+    Paused,
+    Cancelled,
+    Failed(MachineError),
+}
+
+// Memory is shared between forks and only copied on the first write, so
+// forking an executer is cheap.
+pub struct Executer {
+    number: i128,
+    program: Arc<Vec<i128>>,
+    counter: i128,
+    relative: i128,
+    finished: bool,
+    exhausted: bool,
+    interface: Box<dyn MachineInterface>,
+    dynamic_memory: Arc<BTreeMap<i128, i128>>,
+    cancel: Option<Arc<AtomicBool>>,
+    observers: Vec<Box<dyn Observer>>,
+    debug: bool,
+}
+
+impl Executer {
+    pub fn new(
+        number: i128,
+        program: &[i128],
+        interface: Box<dyn MachineInterface>,
+        debug: bool,
+    ) -> Self {
+        Executer {
+            number,
+            program: Arc::new(program.to_owned()),
+            counter: 0,
+            relative: 0,
+            finished: false,
+            exhausted: false,
+            interface,
+            dynamic_memory: Arc::new(BTreeMap::new()),
+            cancel: None,
+            observers: Vec::new(),
+            debug,
+        }
+        .with_tracer()
+    }
+
+    // Continues a run whose state was kept elsewhere, for example by
+    // transpiled code that falls back to the interpreter.
+    pub fn from_state(
+        number: i128,
+        memory: Vec<i128>,
+        dynamic_memory: BTreeMap<i128, i128>,
+        counter: i128,
+        relative: i128,
+        interface: Box<dyn MachineInterface>,
+    ) -> Self {
+        Executer {
+            number,
+            program: Arc::new(memory),
+            counter,
+            relative,
+            finished: false,
+            exhausted: false,
+            interface,
+            dynamic_memory: Arc::new(dynamic_memory),
+            cancel: None,
+            observers: Vec::new(),
+            debug: false,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn with_tracer(mut self) -> Self {
+        if self.debug {
+            let tracer = super::Tracer::new(self.number);
+            self.add_observer(Box::new(tracer));
+        }
+        self
+    }
+
+    // There is nothing to print to, `debug` has no effect.
+    #[cfg(not(feature = "std"))]
+    fn with_tracer(self) -> Self {
+        self
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    // Copies memory, counter and relative base into a new executer that
+    // talks to `interface`. Both continue independently from here on.
+    // Observers are not copied, except for the debug tracer.
+    pub fn fork(&self, interface: Box<dyn MachineInterface>) -> Self {
+        Executer {
+            number: self.number,
+            program: self.program.clone(),
+            counter: self.counter,
+            relative: self.relative,
+            finished: self.finished,
+            exhausted: false,
+            interface,
+            dynamic_memory: self.dynamic_memory.clone(),
+            cancel: None,
+            observers: Vec::new(),
+            debug: self.debug,
+        }
+        .with_tracer()
+    }
+
+    pub fn number(&self) -> i128 {
+        self.number
+    }
+
+    pub fn counter(&self) -> i128 {
+        self.counter
+    }
+
+    pub fn relative(&self) -> i128 {
+        self.relative
+    }
+
+    // Moving the counter also lets a finished run continue from there.
+    pub fn set_counter(&mut self, counter: i128) {
+        self.counter = counter;
+        self.finished = false;
+    }
+
+    pub fn set_relative(&mut self, relative: i128) {
+        self.relative = relative;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // The run stops before the next instruction once the flag is set.
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
+
+    pub fn get(&self, index: i128) -> MachineResult<i128> {
+        let value = if 0 <= index && index < self.program.len() as i128 {
+            Some(self.program[index as usize])
+        } else if self.dynamic_memory.contains_key(&index) {
+            Some(self.dynamic_memory[&index])
+        } else {
+            Some(0)
+        };
+
+        match value {
+            Some(v) => Ok(v),
+            None => Err(MachineError {
+                message: "Index out of bounce!".to_owned(),
+                reason: format!(
+                    "Try to read element {}, but range is 0 <= x < {}.",
+                    index,
+                    self.program.len()
+                ),
+            }),
+        }
+    }
+
+    // The program part of the memory, cells written beyond it are in
+    // `extra_memory`.
+    pub fn memory(&self) -> &[i128] {
+        &self.program
+    }
+
+    // Cells outside the program that were written, ordered by address.
+    pub fn extra_memory(&self) -> Vec<(i128, i128)> {
+        self.dynamic_memory
+            .iter()
+            .map(|(address, value)| (*address, *value))
+            .collect()
+    }
+
+    // Writes memory directly, observers do not see it.
+    pub fn set(&mut self, index: i128, value: i128) -> MachineResult<()> {
+        let value = if 0 <= index && index < self.program.len() as i128 {
+            Arc::make_mut(&mut self.program)[index as usize] = value;
+            Some(())
+        } else {
+            Arc::make_mut(&mut self.dynamic_memory).insert(index, value);
+            Some(())
+        };
+
+        match value {
+            Some(_) => Ok(()),
+            None => Err(MachineError {
+                message: "Index out of bounce!".to_owned(),
+                reason: format!(
+                    "Try to write element {}, but range is 0 <= x < {}.",
+                    index,
+                    self.program.len()
+                ),
+            }),
+        }
+    }
+
+    // Intcode numbers are unbounded, the machine fails where an i128 ends.
+    fn checked(&self, value: Option<i128>) -> MachineResult<i128> {
+        value.ok_or_else(|| MachineError {
+            message: "Arithmetic overflow!".to_owned(),
+            reason: format!(
+                "The instruction at index {} leaves the range of an i128.",
+                self.counter
+            ),
+        })
+    }
+
+    // Moves the counter past an instruction of `length` words.
+    fn advance(&mut self, length: i128) -> MachineResult<()> {
+        self.counter = self.checked(self.counter.checked_add(length))?;
+        Ok(())
+    }
+
+    fn param(&mut self, position: i128, code: &OpCode) -> MachineResult<i128> {
+        let index = self.checked(self.counter.checked_add(position))?;
+        let value = self.get(index)?;
+
+        let address = match code.mode(position) {
+            0 => value,
+            1 => return Ok(value),
+            2 => self.checked(value.checked_add(self.relative))?,
+            _ => {
+                return Err(MachineError {
+                    message: "Illegal parameter mode!".to_owned(),
+                    reason: format!(
+                        "Found parameter mode '{}' at index {}.",
+                        code.mode(position),
+                        index
+                    ),
+                })
+            }
+        };
+
+        let value = self.get(address)?;
+        self.notify(|observer, executer| observer.read(executer, address, value));
+
+        Ok(value)
+    }
+
+    fn set_param(&mut self, position: i128, code: &OpCode, value: i128) -> MachineResult<()> {
+        let index = self.checked(self.counter.checked_add(position))?;
+        let v = self.get(index)?;
+
+        let address = match code.mode(position) {
+            0 => v,
+            2 => self.checked(v.checked_add(self.relative))?,
+            _ => {
+                return Err(MachineError {
+                    message: "Illegal parameter mode!".to_owned(),
+                    reason: format!(
+                        "Found parameter mode '{}' at index {}.",
+                        code.mode(position),
+                        index
+                    ),
+                })
+            }
+        };
+
+        self.notify(|observer, executer| observer.write(executer, address, value));
+        self.set(address, value)
+    }
+
+    fn notify<F: FnMut(&mut dyn Observer, &Executer)>(&mut self, mut event: F) {
+        if self.observers.is_empty() {
+            return;
+        }
+
+        // Observers get a view of the executer, so they are moved out while
+        // they are called.
+        let mut observers = core::mem::take(&mut self.observers);
+        for observer in observers.iter_mut() {
+            event(observer.as_mut(), self);
+        }
+        self.observers = observers;
+    }
+
+    fn pause_requested(&mut self) -> bool {
+        let mut pause = false;
+        self.notify(|observer, executer| pause |= observer.pause(executer));
+        pause
+    }
+
+    // Runs until the program stops and reports the outcome to the interface.
+    // An exhausted or paused run stops in front of the next instruction, so
+    // it can be continued by calling `execute` again.
+    pub fn execute(&mut self) -> RunOutcome {
+        let outcome = self.execute_steps(None);
+        self.finish(outcome)
+    }
+
+    // Executes a single instruction, a run that can go on reports `Paused`.
+    pub fn step(&mut self) -> RunOutcome {
+        let outcome = self.execute_steps(Some(1));
+        self.finish(outcome)
+    }
+
+    fn finish(&mut self, outcome: RunOutcome) -> RunOutcome {
+        if let RunOutcome::Paused = outcome {
+            return outcome;
+        }
+
+        let outcome = match self.interface.halt(&outcome) {
+            Ok(()) => outcome,
+            Err(error) => match outcome {
+                RunOutcome::Failed(_) => outcome,
+                _ => RunOutcome::Failed(error),
+            },
+        };
+
+        self.notify(|observer, executer| observer.halt(executer, &outcome));
+        outcome
+    }
+
+    fn execute_steps(&mut self, limit: Option<usize>) -> RunOutcome {
+        self.exhausted = false;
+
+        let mut steps = 0;
+        while !self.finished {
+            if let Some(cancel) = &self.cancel {
+                if cancel.load(Ordering::Relaxed) {
+                    return RunOutcome::Cancelled;
+                }
+            }
+
+            // Also asked in front of the first instruction. Observers that
+            // pause at an address have to let a resumed run past it.
+            if limit == Some(steps) || self.pause_requested() {
+                return RunOutcome::Paused;
+            }
+
+            if let Err(error) = self.perform_step() {
+                return RunOutcome::Failed(error);
+            }
+
+            if self.exhausted {
+                return RunOutcome::Exhausted;
+            }
+
+            steps += 1;
+        }
+
+        RunOutcome::Halted
+    }
+
+    #[cfg(feature = "std")]
+    pub fn run(&mut self) -> bool {
+        match self.execute() {
+            RunOutcome::Halted => true,
+            RunOutcome::Exhausted => {
+                println!("Executer[{}]: Input exhausted!", self.number);
+                false
+            }
+            RunOutcome::Paused => {
+                println!("Executer[{}]: Paused!", self.number);
+                false
+            }
+            RunOutcome::Cancelled => {
+                println!("Executer[{}]: Cancelled!", self.number);
+                false
+            }
+            RunOutcome::Failed(error) => {
+                let index = self.counter;
+                println!("Executer[{}]: Exception at index {}!", self.number, index);
+                let words: Vec<i128> = (0..4)
+                    .filter_map(|offset| index.checked_add(offset))
+                    .map(|address| self.get(address).unwrap_or(0))
+                    .collect();
+                println!("Executer[{}]: Program: {:?}", self.number, words);
+                println!("Executer[{}]: {:?}", self.number, error);
+
+                false
+            }
+        }
+    }
+
+    fn perform_step(&mut self) -> MachineResult<()> {
+        let code = OpCode::new(self.get(self.counter)?);
+        self.notify(|observer, executer| observer.instruction(executer, &code));
+
+        match code.op() {
+            1 => {
+                // Add
+                let param_1 = self.param(1, &code)?;
+                let param_2 = self.param(2, &code)?;
+
+                let sum = self.checked(param_1.checked_add(param_2))?;
+                self.set_param(3, &code, sum)?;
+                self.advance(4)?;
+            }
+            2 => {
+                // Multiply
+                let param_1 = self.param(1, &code)?;
+                let param_2 = self.param(2, &code)?;
+
+                let product = self.checked(param_1.checked_mul(param_2))?;
+                self.set_param(3, &code, product)?;
+                self.advance(4)?;
+            }
+            3 => {
+                // Input
+                let value = match self.interface.receive()? {
+                    Some(value) => value,
+                    None => {
+                        self.exhausted = true;
+                        return Ok(());
+                    }
+                };
+                self.notify(|observer, executer| observer.input(executer, value));
+
+                self.set_param(1, &code, value)?;
+                self.advance(2)?;
+            }
+            4 => {
+                // Output
+                let param_1 = self.param(1, &code)?;
+                self.notify(|observer, executer| observer.output(executer, param_1));
+
+                self.interface.send(param_1)?;
+                self.advance(2)?;
+            }
+            5 => {
+                // jump if true
+                let param_1 = self.param(1, &code)?;
+                let param_2 = self.param(2, &code)?;
+
+                if param_1 != 0 {
+                    self.counter = param_2;
+                } else {
+                    self.advance(3)?;
+                }
+            }
+            6 => {
+                // jump if false
+                let param_1 = self.param(1, &code)?;
+                let param_2 = self.param(2, &code)?;
+
+                if param_1 == 0 {
+                    self.counter = param_2;
+                } else {
+                    self.advance(3)?;
+                }
+            }
+            7 => {
+                // less than
+                let param_1 = self.param(1, &code)?;
+                let param_2 = self.param(2, &code)?;
+
+                self.set_param(3, &code, (param_1 < param_2) as i128)?;
+                self.advance(4)?;
+            }
+            8 => {
+                // equals than
+                let param_1 = self.param(1, &code)?;
+                let param_2 = self.param(2, &code)?;
+
+                self.set_param(3, &code, (param_1 == param_2) as i128)?;
+                self.advance(4)?;
+            }
+            9 => {
+                // set relative
+                let param_1 = self.param(1, &code)?;
+
+                let relative = self.checked(self.relative.checked_add(param_1))?;
+                self.notify(|observer, executer| observer.relative(executer, relative));
+
+                self.relative = relative;
+                self.advance(2)?;
+            }
+            99 => {
+                self.finished = true;
+            }
+            _ => {
+                return Err(MachineError {
+                    message: "Illegal OpCode!".to_owned(),
+                    reason: format!("Found OpCode '{}' at index {}.", code.op(), self.counter),
+                });
+            }
+        };
+
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+use std::io::{BufReader, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+
+use super::{Debugger, MachineError, MachineResult, PauseRequest, RunOutcome, StopReason, Watch};
+
+// Memory is byte addressed for the client, every word takes 16 little
+// endian bytes. The registers and breakpoints use the same byte addresses,
+// so `x/i $pc` reads the current instruction.
+const WORD_BYTES: i128 = 16;
+// The largest packet the stub accepts or sends, as told to the client.
+const PACKET_SIZE: usize = 0x1000;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.intcode.core">
+    <reg name="counter" bitsize="128" type="int128" regnum="0"/>
+    <reg name="relative" bitsize="128" type="int128" regnum="1"/>
+  </feature>
+</target>
+"#;
+
+enum Incoming {
+    Packet(String),
+    // The client sent ^C.
+    Interrupt,
+}
+
+fn read_packets<R: Read>(reader: R, interrupt: Arc<AtomicBool>, sender: mpsc::Sender<Incoming>) {
+    let mut bytes = BufReader::new(reader).bytes();
+
+    while let Some(Ok(byte)) = bytes.next() {
+        let incoming = match byte {
+            b'$' => {
+                let mut packet = Vec::new();
+                loop {
+                    match bytes.next() {
+                        Some(Ok(b'#')) => break,
+                        Some(Ok(byte)) => packet.push(byte),
+                        _ => return,
+                    }
+                }
+                // The checksum is not checked, the transport is reliable.
+                if bytes.next().is_none() || bytes.next().is_none() {
+                    return;
+                }
+                Incoming::Packet(String::from_utf8_lossy(&packet).into_owned())
+            }
+            3 => {
+                interrupt.store(true, Ordering::Relaxed);
+                Incoming::Interrupt
+            }
+            // Acknowledgements.
+            _ => continue,
+        };
+
+        if sender.send(incoming).is_err() {
+            return;
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+fn word_from_hex(text: &str) -> Option<i128> {
+    let bytes = from_hex(text)?;
+    if bytes.len() != WORD_BYTES as usize {
+        return None;
+    }
+    let mut word = [0; WORD_BYTES as usize];
+    word.copy_from_slice(&bytes);
+    Some(i128::from_le_bytes(word))
+}
+
+// The word at a byte address, which has to be the first byte of the word.
+fn word_address(address: i128) -> Option<i128> {
+    if address.rem_euclid(WORD_BYTES) == 0 {
+        Some(address.div_euclid(WORD_BYTES))
+    } else {
+        None
+    }
+}
+
+// Parses `addr,length` as sent by `m`, `M`, `Z` and `z`.
+fn address_length(text: &str) -> Option<(i128, i128)> {
+    let mut parts = text.split(',');
+    let address = i128::from_str_radix(parts.next()?, 16).ok()?;
+    let length = i128::from_str_radix(parts.next()?, 16).ok()?;
+    Some((address, length))
+}
+
+struct GdbStub<'a, W: Write> {
+    debugger: &'a mut Debugger,
+    writer: W,
+    last_stop: String,
+}
+
+impl<'a, W: Write> GdbStub<'a, W> {
+    fn send(&mut self, packet: &str) -> MachineResult<()> {
+        let checksum = packet.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        write!(self.writer, "${}#{:02x}", packet, checksum)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn stop_reply(&mut self, outcome: RunOutcome) -> String {
+        let reply = match outcome {
+            RunOutcome::Halted => "W00".to_owned(),
+            // Reported as a segmentation fault, the state can still be
+            // inspected.
+            RunOutcome::Failed(_) => "S0b".to_owned(),
+            RunOutcome::Exhausted => "S05".to_owned(),
+            RunOutcome::Paused | RunOutcome::Cancelled => match self.debugger.reason() {
+                Some(StopReason::Breakpoint(_)) => "T05swbreak:;".to_owned(),
+                Some(StopReason::Watch { watch, address, .. }) => {
+                    let kind = match watch {
+                        Watch::Read(_) => "rwatch",
+                        Watch::Access(_) => "awatch",
+                        Watch::Write(_) | Watch::WriteValue(_, _) => "watch",
+                    };
+                    format!("T05{}:{:x};", kind, address * WORD_BYTES)
+                }
+                Some(_) => "S05".to_owned(),
+                None => "S02".to_owned(),
+            },
+        };
+
+        self.last_stop = reply.clone();
+        reply
+    }
+
+    // Reads at most what fits into one reply and stops at the end of the
+    // address space, the client asks again for the rest.
+    fn read_memory(&self, address: i128, length: i128) -> String {
+        let executer = self.debugger.executer();
+        let length = length.min(PACKET_SIZE as i128 / 2);
+        let bytes: Vec<u8> = (address..address.saturating_add(length))
+            .map(|byte| {
+                let word = executer.get(byte.div_euclid(WORD_BYTES)).unwrap_or(0);
+                word.to_le_bytes()[byte.rem_euclid(WORD_BYTES) as usize]
+            })
+            .collect();
+        to_hex(&bytes)
+    }
+
+    fn write_memory(&mut self, address: i128, data: &[u8]) -> MachineResult<()> {
+        let executer = self.debugger.executer_mut();
+        for (offset, byte) in data.iter().enumerate() {
+            let target = address
+                .checked_add(offset as i128)
+                .ok_or_else(|| MachineError {
+                    message: "Invalid address!".to_owned(),
+                    reason: "The write leaves the address space.".to_owned(),
+                })?;
+            let index = target.div_euclid(WORD_BYTES);
+
+            let mut word = executer.get(index)?.to_le_bytes();
+            word[target.rem_euclid(WORD_BYTES) as usize] = *byte;
+            executer.set(index, i128::from_le_bytes(word))?;
+        }
+        Ok(())
+    }
+
+    // Both registers are byte addresses like everything else the client
+    // sees, `$pc` is the counter times `WORD_BYTES`. That way `x/i $pc`,
+    // `break *$pc` and the stop replies agree on one address space.
+    fn register(&self, number: &str) -> Option<i128> {
+        let executer = self.debugger.executer();
+        match i128::from_str_radix(number, 16).ok()? {
+            0 => executer.counter().checked_mul(WORD_BYTES),
+            1 => executer.relative().checked_mul(WORD_BYTES),
+            _ => None,
+        }
+    }
+
+    fn set_register(&mut self, number: &str, value: i128) -> bool {
+        let executer = self.debugger.executer_mut();
+        match (i128::from_str_radix(number, 16), word_address(value)) {
+            (Ok(0), Some(address)) => executer.set_counter(address),
+            (Ok(1), Some(address)) => executer.set_relative(address),
+            _ => return false,
+        }
+        true
+    }
+
+    // Watches cover every word the watched bytes touch.
+    fn watches(kind: &str, address: i128, length: i128) -> Vec<Watch> {
+        let first = address.div_euclid(WORD_BYTES);
+        let last = address
+            .saturating_add(length.max(1) - 1)
+            .div_euclid(WORD_BYTES);
+        (first..=last)
+            .map(|word| match kind {
+                "2" => Watch::Write(word),
+                "3" => Watch::Read(word),
+                _ => Watch::Access(word),
+            })
+            .collect()
+    }
+
+    // `None` if the kind is not supported, `false` if a breakpoint is not
+    // at the start of a word.
+    fn breakpoint(&mut self, insert: bool, arguments: &str) -> Option<bool> {
+        let mut parts = arguments.splitn(2, ',');
+        let kind = parts.next()?;
+        let (address, length) = address_length(parts.next()?)?;
+
+        match (kind, word_address(address)) {
+            ("0" | "1", None) => return Some(false),
+            ("0" | "1", Some(word)) if insert => self.debugger.add_breakpoint(word),
+            ("0" | "1", Some(word)) => self.debugger.remove_breakpoint(word),
+            ("2" | "3" | "4", _) => {
+                for watch in Self::watches(kind, address, length) {
+                    if insert {
+                        self.debugger.add_watch(watch);
+                    } else {
+                        self.debugger.remove_watch(watch);
+                    }
+                }
+            }
+            _ => return None,
+        }
+        Some(true)
+    }
+
+    fn features(&self, arguments: &str) -> Option<String> {
+        let range = arguments.strip_prefix("target.xml:")?;
+        let (offset, length) = address_length(range)?;
+        let offset = offset.clamp(0, TARGET_XML.len() as i128) as usize;
+        let length = length.clamp(0, PACKET_SIZE as i128 - 1) as usize;
+        let end = (offset + length).min(TARGET_XML.len());
+
+        let prefix = if end == TARGET_XML.len() { "l" } else { "m" };
+        Some(format!("{}{}", prefix, &TARGET_XML[offset..end]))
+    }
+
+    // The reply to a packet, `None` once the client is gone.
+    fn handle(&mut self, packet: &str) -> MachineResult<Option<String>> {
+        let error = || "E01".to_owned();
+        let ok = || "OK".to_owned();
+
+        let first = packet.chars().next().map_or(0, char::len_utf8);
+        let (command, arguments) = packet.split_at(first);
+        let reply = match command {
+            "?" => self.last_stop.clone(),
+            "g" => match (self.register("0"), self.register("1")) {
+                (Some(counter), Some(relative)) => format!(
+                    "{}{}",
+                    to_hex(&counter.to_le_bytes()),
+                    to_hex(&relative.to_le_bytes())
+                ),
+                _ => error(),
+            },
+            "G" => {
+                let split = arguments.len().min(2 * WORD_BYTES as usize);
+                match (
+                    arguments.get(..split).and_then(word_from_hex),
+                    arguments.get(split..).and_then(word_from_hex),
+                ) {
+                    (Some(counter), Some(relative))
+                        if word_address(counter).is_some() && word_address(relative).is_some() =>
+                    {
+                        self.set_register("0", counter);
+                        self.set_register("1", relative);
+                        ok()
+                    }
+                    _ => error(),
+                }
+            }
+            "p" => self
+                .register(arguments)
+                .map_or_else(error, |value| to_hex(&value.to_le_bytes())),
+            "P" => {
+                let mut parts = arguments.splitn(2, '=');
+                let number = parts.next().unwrap_or("");
+                match parts.next().and_then(word_from_hex) {
+                    Some(value) if self.set_register(number, value) => ok(),
+                    _ => error(),
+                }
+            }
+            "m" => address_length(arguments)
+                .map_or_else(error, |(address, length)| self.read_memory(address, length)),
+            "M" => {
+                let mut parts = arguments.splitn(2, ':');
+                let target = parts.next().and_then(address_length);
+                let data = parts.next().and_then(from_hex);
+                match (target, data) {
+                    (Some((address, length)), Some(data)) if data.len() as i128 == length => self
+                        .write_memory(address, &data)
+                        .map_or_else(|_| error(), |_| ok()),
+                    _ => error(),
+                }
+            }
+            "Z" | "z" => self
+                .breakpoint(command == "Z", arguments)
+                .map_or_else(String::new, |set| if set { ok() } else { error() }),
+            "c" | "s" => {
+                if !arguments.is_empty() {
+                    let address = i128::from_str_radix(arguments, 16).ok();
+                    if !address.is_some_and(|address| self.set_register("0", address)) {
+                        return Ok(Some(error()));
+                    }
+                }
+                let outcome = if command == "c" {
+                    self.debugger.resume()
+                } else {
+                    self.debugger.step()
+                };
+                self.stop_reply(outcome)
+            }
+            "H" | "T" => ok(),
+            "D" => {
+                self.send("OK")?;
+                return Ok(None);
+            }
+            "k" => return Ok(None),
+            _ => {
+                if packet.starts_with("qSupported") {
+                    format!("PacketSize={:x};qXfer:features:read+;swbreak+", PACKET_SIZE)
+                } else if let Some(arguments) = packet.strip_prefix("qXfer:features:read:") {
+                    self.features(arguments).unwrap_or_else(error)
+                } else {
+                    match packet {
+                        "qAttached" => "1".to_owned(),
+                        "qC" => "QC1".to_owned(),
+                        "qfThreadInfo" => "m1".to_owned(),
+                        "qsThreadInfo" => "l".to_owned(),
+                        // Everything else is unsupported, which is an empty
+                        // reply.
+                        _ => String::new(),
+                    }
+                }
+            }
+        };
+
+        Ok(Some(reply))
+    }
+}
+
+// Serves the GDB remote serial protocol for the machine in `debugger` until
+// the client detaches or kills it. A ^C from the client pauses a running
+// program.
+pub fn serve_gdb<R, W>(debugger: &mut Debugger, reader: R, writer: W) -> MachineResult<()>
+where
+    R: Read + Send + 'static,
+    W: Write,
+{
+    let interrupt = Arc::new(AtomicBool::new(false));
+    debugger
+        .executer_mut()
+        .add_observer(Box::new(PauseRequest::new(interrupt.clone())));
+
+    let (sender, receiver) = mpsc::channel();
+    let flag = interrupt.clone();
+    std::thread::spawn(move || read_packets(reader, flag, sender));
+
+    let mut stub = GdbStub {
+        debugger,
+        writer,
+        last_stop: "S05".to_owned(),
+    };
+
+    for incoming in receiver {
+        match incoming {
+            // A ^C that arrives after the program stopped anyway.
+            Incoming::Interrupt => interrupt.store(false, Ordering::Relaxed),
+            Incoming::Packet(packet) => {
+                stub.writer.write_all(b"+")?;
+                match stub.handle(&packet)? {
+                    Some(reply) => stub.send(&reply)?,
+                    None => break,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Waits for a single client on `address`, for example `127.0.0.1:1234`.
+pub fn serve_gdb_tcp(debugger: &mut Debugger, address: &str) -> MachineResult<()> {
+    let listener = std::net::TcpListener::bind(address)?;
+    let (stream, _) = listener.accept()?;
+
+    let result = serve_gdb(debugger, stream.try_clone()?, &stream);
+    stream.shutdown(std::net::Shutdown::Both)?;
+    result
+}
+
+#[cfg(unix)]
+pub fn serve_gdb_unix(debugger: &mut Debugger, path: &str) -> MachineResult<()> {
+    let listener = std::os::unix::net::UnixListener::bind(path)?;
+    let (stream, _) = listener.accept()?;
+
+    let result = serve_gdb(debugger, stream.try_clone()?, &stream);
+    stream.shutdown(std::net::Shutdown::Both)?;
+    result
+}
@@ -0,0 +1,196 @@
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "std")]
+use super::{channel_with_mode, ChannelMode, ChannelReceiver, ChannelSender, ChannelStats};
+use super::{MachineResult, RunOutcome};
+
+pub trait MachineInterface: Send {
+    fn send(&mut self, value: i128) -> MachineResult<()>;
+
+    // `None` signals that no more input will arrive.
+    fn receive(&mut self) -> MachineResult<Option<i128>>;
+
+    // Called once every time the executer stops running.
+    fn halt(&mut self, _outcome: &RunOutcome) -> MachineResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+pub struct ChannelInterface {
+    pub in_sender: ChannelSender,
+    in_receiver: ChannelReceiver,
+    out_sender: ChannelSender,
+    pub out_receiver: ChannelReceiver,
+}
+
+#[cfg(feature = "std")]
+impl ChannelInterface {
+    pub fn new() -> Self {
+        ChannelInterface::with_mode(ChannelMode::Unbounded)
+    }
+
+    pub fn with_mode(mode: ChannelMode) -> Self {
+        let (in_sender, in_receiver) = channel_with_mode(mode);
+        let (out_sender, out_receiver) = channel_with_mode(mode);
+
+        ChannelInterface {
+            in_sender,
+            in_receiver,
+            out_sender,
+            out_receiver,
+        }
+    }
+
+    pub fn in_stats(&self) -> ChannelStats {
+        self.in_receiver.stats()
+    }
+
+    pub fn out_stats(&self) -> ChannelStats {
+        self.out_sender.stats()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for ChannelInterface {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl MachineInterface for ChannelInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        self.out_sender.send(value)
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        Ok(self.in_receiver.recv())
+    }
+
+    // Exhausted and cancelled runs can be resumed and write more output.
+    fn halt(&mut self, outcome: &RunOutcome) -> MachineResult<()> {
+        if let RunOutcome::Halted | RunOutcome::Failed(_) = outcome {
+            self.out_sender.halt(outcome.clone());
+        }
+        Ok(())
+    }
+}
+
+// Feeds every line read as its code points followed by a newline, and
+// writes outputs below 128 as text, larger ones as numbers on a line of
+// their own. Reads stdin and writes stdout unless told otherwise.
+#[cfg(feature = "std")]
+pub struct AsciiInterface {
+    reader: Box<dyn BufRead + Send>,
+    writer: Box<dyn Write + Send>,
+    buffer: VecDeque<i128>,
+}
+
+#[cfg(feature = "std")]
+impl AsciiInterface {
+    pub fn new() -> Self {
+        AsciiInterface::with_io(BufReader::new(std::io::stdin()), std::io::stdout())
+    }
+
+    pub fn with_io<R, W>(reader: R, writer: W) -> Self
+    where
+        R: BufRead + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        AsciiInterface {
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for AsciiInterface {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl MachineInterface for AsciiInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        if (0..128).contains(&value) {
+            write!(self.writer, "{}", value as u8 as char)?;
+        } else {
+            writeln!(self.writer, "{}", value)?;
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        // A prompt is shown before the answer is read.
+        self.writer.flush()?;
+
+        if self.buffer.is_empty() {
+            let mut input = String::new();
+            if self.reader.read_line(&mut input)? == 0 {
+                return Ok(None);
+            }
+
+            let line = input.trim_end_matches(['\n', '\r']);
+            self.buffer.extend(line.chars().map(|c| c as i128));
+            self.buffer.push_back(10);
+        }
+
+        Ok(self.buffer.pop_front())
+    }
+
+    fn halt(&mut self, _outcome: &RunOutcome) -> MachineResult<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// Input is whatever was pushed so far, once it runs out the program stops
+// as exhausted and can be continued after more is pushed. Clones share
+// their queues, so one can be handed to the executer and the other kept.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct QueueInterface {
+    input: Arc<Mutex<VecDeque<i128>>>,
+    output: Arc<Mutex<Vec<i128>>>,
+}
+
+#[cfg(feature = "std")]
+impl QueueInterface {
+    pub fn new() -> Self {
+        QueueInterface::default()
+    }
+
+    pub fn push_input(&self, values: &[i128]) {
+        self.input.lock().unwrap().extend(values);
+    }
+
+    pub fn pending_input(&self) -> usize {
+        self.input.lock().unwrap().len()
+    }
+
+    // All output since the last call.
+    pub fn take_output(&self) -> Vec<i128> {
+        std::mem::take(&mut *self.output.lock().unwrap())
+    }
+}
+
+#[cfg(feature = "std")]
+impl MachineInterface for QueueInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        self.output.lock().unwrap().push(value);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        Ok(self.input.lock().unwrap().pop_front())
+    }
+}
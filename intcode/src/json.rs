@@ -0,0 +1,305 @@
+use super::{MachineError, MachineResult};
+
+// Just enough JSON for the debug and control protocols. Numbers are kept
+// as integers, since that is all Intcode deals with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i128),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+const NULL: Json = Json::Null;
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    // The field `key`, `Null` if there is none.
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&NULL, |(_, value)| value),
+            _ => &NULL,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Json::Null
+    }
+
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn parse(text: &str) -> MachineResult<Self> {
+        let mut parser = JsonParser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("Trailing characters"));
+        }
+        Ok(value)
+    }
+}
+
+impl From<i128> for Json {
+    fn from(value: i128) -> Self {
+        Json::Number(value)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(values: Vec<Json>) -> Self {
+        Json::Array(values)
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter, value: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn error(&self, reason: &str) -> MachineError {
+        MachineError {
+            message: "Illegal JSON!".to_owned(),
+            reason: format!("{} at character {}.", reason, self.position),
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while self.position < self.chars.len() && self.chars[self.position].is_whitespace() {
+            self.position += 1;
+        }
+    }
+
+    fn next(&mut self) -> MachineResult<char> {
+        let c = *self
+            .chars
+            .get(self.position)
+            .ok_or_else(|| self.error("Unexpected end"))?;
+        self.position += 1;
+        Ok(c)
+    }
+
+    fn expect(&mut self, word: &str) -> MachineResult<()> {
+        for c in word.chars() {
+            if self.next()? != c {
+                return Err(self.error(&format!("Expected '{}'", word)));
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&mut self) -> MachineResult<Json> {
+        self.whitespace();
+        match self.chars.get(self.position) {
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.error("Unexpected character")),
+        }
+    }
+
+    // Fractions and exponents are accepted, but the value is truncated.
+    fn number(&mut self) -> MachineResult<Json> {
+        let start = self.position;
+        while self.position < self.chars.len()
+            && (self.chars[self.position].is_ascii_digit()
+                || "+-.eE".contains(self.chars[self.position]))
+        {
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        if let Ok(value) = text.parse::<i128>() {
+            return Ok(Json::Number(value));
+        }
+        text.parse::<f64>()
+            .map(|value| Json::Number(value as i128))
+            .map_err(|_| self.error("Illegal number"))
+    }
+
+    fn string(&mut self) -> MachineResult<String> {
+        self.expect("\"")?;
+        let mut value = String::new();
+
+        loop {
+            match self.next()? {
+                '"' => return Ok(value),
+                '\\' => match self.next()? {
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'u' => {
+                        let hex: String =
+                            (0..4).map(|_| self.next()).collect::<MachineResult<_>>()?;
+                        let code = u32::from_str_radix(&hex, 16)
+                            .map_err(|_| self.error("Illegal escape"))?;
+                        value.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    c => value.push(c),
+                },
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> MachineResult<Json> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+
+        self.whitespace();
+        if self.chars.get(self.position) == Some(&']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.next()? {
+                ',' => {}
+                ']' => return Ok(Json::Array(values)),
+                _ => return Err(self.error("Expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> MachineResult<Json> {
+        self.expect("{")?;
+        let mut fields = Vec::new();
+
+        self.whitespace();
+        if self.chars.get(self.position) == Some(&'}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(":")?;
+            fields.push((key, self.value()?));
+            self.whitespace();
+            match self.next()? {
+                ',' => {}
+                '}' => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("Expected ',' or '}'")),
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// Without the default `std` feature only the executer, its interfaces and
+// observers and the disassembler are built, on top of `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+mod asm;
+#[cfg(feature = "std")]
+mod calls;
+#[cfg(feature = "std")]
+mod channel;
+#[cfg(feature = "std")]
+mod compile;
+#[cfg(feature = "std")]
+mod coverage;
+#[cfg(feature = "std")]
+mod dap;
+#[cfg(feature = "std")]
+mod debugger;
+mod disasm;
+mod error;
+mod executer;
+#[cfg(feature = "std")]
+mod gdb;
+mod interface;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod machine;
+mod observer;
+#[cfg(feature = "std")]
+mod optimize;
+#[cfg(feature = "std")]
+mod patch;
+#[cfg(feature = "std")]
+mod pipeline;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "std")]
+mod rpc;
+#[cfg(feature = "std")]
+mod search;
+#[cfg(feature = "std")]
+mod selfmod;
+#[cfg(feature = "std")]
+mod tcp;
+#[cfg(feature = "std")]
+mod topology;
+#[cfg(feature = "std")]
+mod transpile;
+mod utils;
+
+#[cfg(feature = "std")]
+pub use asm::*;
+#[cfg(feature = "std")]
+pub use calls::*;
+#[cfg(feature = "std")]
+pub use channel::*;
+#[cfg(feature = "std")]
+pub use compile::*;
+#[cfg(feature = "std")]
+pub use coverage::*;
+#[cfg(feature = "std")]
+pub use dap::*;
+#[cfg(feature = "std")]
+pub use debugger::*;
+pub use disasm::*;
+pub use error::*;
+pub use executer::*;
+#[cfg(feature = "std")]
+pub use gdb::*;
+pub use interface::*;
+#[cfg(feature = "std")]
+pub use json::*;
+#[cfg(feature = "std")]
+pub use machine::*;
+pub use observer::*;
+#[cfg(feature = "std")]
+pub use optimize::*;
+#[cfg(feature = "std")]
+pub use patch::*;
+#[cfg(feature = "std")]
+pub use pipeline::*;
+#[cfg(feature = "std")]
+pub use pool::*;
+#[cfg(feature = "std")]
+pub use record::*;
+#[cfg(feature = "std")]
+pub use rpc::*;
+#[cfg(feature = "std")]
+pub use search::*;
+#[cfg(feature = "std")]
+pub use selfmod::*;
+#[cfg(feature = "std")]
+pub use tcp::*;
+#[cfg(feature = "std")]
+pub use topology::*;
+#[cfg(feature = "std")]
+pub use transpile::*;
+pub use utils::*;
+
+#[cfg(feature = "std")]
+pub fn parse_file(path: &str) -> MachineResult<Vec<i128>> {
+    Ok(parse(&std::fs::read_to_string(path)?))
+}
+
+pub fn parse(data: &str) -> Vec<i128> {
+    data.split(',')
+        .filter_map(|s| s.trim().parse::<i128>().ok())
+        .collect()
+}
@@ -0,0 +1,63 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use super::{Executer, MachineHandle, MachineInterface, MachineResult, Topology, WorkerPool};
+
+#[derive(Debug)]
+pub struct Machine {
+    pub program: Vec<i128>,
+    executer_count: i128,
+    pool: Option<Arc<WorkerPool>>,
+}
+
+impl Machine {
+    pub fn new(program: &[i128]) -> Self {
+        Machine {
+            program: program.to_owned(),
+            executer_count: 0,
+            pool: None,
+        }
+    }
+
+    pub fn with_pool(program: &[i128], pool: Arc<WorkerPool>) -> Self {
+        Machine {
+            program: program.to_owned(),
+            executer_count: 0,
+            pool: Some(pool),
+        }
+    }
+
+    // Without a pool the executer runs inline and the returned handle is
+    // already finished. With a pool it is queued and runs once a worker is free.
+    pub fn spawn(&mut self, interface: Box<dyn MachineInterface>) -> MachineHandle {
+        let number = self.executer_count;
+        let program = self.program.clone();
+        self.executer_count += 1;
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+
+        let flag = cancel.clone();
+        let job = move || {
+            let mut executer = Executer::new(number, &program, interface, false);
+            executer.set_cancel_flag(flag);
+
+            let _ = sender.send(executer.execute());
+        };
+
+        match &self.pool {
+            Some(pool) => pool.execute(job),
+            None => job(),
+        }
+
+        MachineHandle::new(number, cancel, receiver)
+    }
+
+    pub fn run_network(&mut self, topology: &Topology) -> MachineResult<Vec<Option<i128>>> {
+        let first_number = self.executer_count;
+        self.executer_count += topology.nodes.len() as i128;
+
+        topology.run(&self.program, first_number)
+    }
+}
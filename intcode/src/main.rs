@@ -0,0 +1,374 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+
+use intcode::{
+    assemble, disassemble, parse, serve_dap_stdio, serve_gdb_tcp, serve_rpc_stdio, serve_rpc_tcp,
+    AsciiInterface, CallStack, Coverage, Debugger, Executer, MachineError, MachineInterface,
+    MachineResult, RunOutcome, SelfModification, StepLimit, TcpInterface,
+};
+
+const USAGE: &str = "Usage:
+    intcode run <program> [--input 1,2,3] [--ascii] [--trace] [--max-steps N] [--set 1=12]...
+                          [--coverage FILE] [--self-modification] [--profile FILE] [--read 0,1]
+                          [--listen 127.0.0.1:4000 | --connect 127.0.0.1:4000]
+    intcode disassemble <program>
+    intcode assemble <source>
+    intcode dap
+    intcode rpc [--listen 127.0.0.1:4000]
+    intcode gdb <program> --listen 127.0.0.1:1234
+
+Programs are comma separated files, or assembler listings if they end in
+'.asm'. Without --input, `run` reads input from the terminal: integers
+separated by commas or whitespace, or lines of text with --ascii.
+--read prints the given memory cells once the run stopped, as
+address=value. --coverage writes a coverage report and an annotated listing with the
+hit count of every instruction. --self-modification reports every write
+into code on stderr. --profile writes the instructions executed per call
+stack in the folded format flamegraph tools read. With --listen or
+--connect, `run` talks to a TCP peer instead of the terminal, one value
+per line. `rpc` clients on --listen cannot load programs by path.";
+
+fn usage_error(reason: String) -> MachineError {
+    MachineError {
+        message: "Illegal arguments!".to_owned(),
+        reason,
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    command: String,
+    path: Option<String>,
+    input: Option<Vec<i128>>,
+    ascii: bool,
+    trace: bool,
+    max_steps: Option<u64>,
+    patches: Vec<(i128, i128)>,
+    reads: Vec<i128>,
+    coverage: Option<String>,
+    self_modification: bool,
+    profile: Option<String>,
+    listen: Option<String>,
+    connect: Option<String>,
+}
+
+fn parse_value(text: &str) -> MachineResult<i128> {
+    text.trim()
+        .parse::<i128>()
+        .map_err(|_| usage_error(format!("'{}' is not an integer.", text)))
+}
+
+fn parse_options(args: &[String]) -> MachineResult<Options> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    options.command = args
+        .next()
+        .ok_or_else(|| usage_error("Missing command.".to_owned()))?
+        .clone();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| usage_error(format!("{} needs a value.", name)))
+        };
+
+        match arg.as_str() {
+            "--input" => {
+                let values = value("--input")?
+                    .split(',')
+                    .filter(|v| !v.trim().is_empty())
+                    .map(parse_value)
+                    .collect::<MachineResult<_>>()?;
+                options.input = Some(values);
+            }
+            "--ascii" => options.ascii = true,
+            "--trace" => options.trace = true,
+            "--max-steps" => {
+                let steps = value("--max-steps")?;
+                let steps = steps
+                    .parse::<u64>()
+                    .map_err(|_| usage_error(format!("'{}' is not a step count.", steps)))?;
+                options.max_steps = Some(steps);
+            }
+            "--set" => {
+                for patch in value("--set")?.split(',') {
+                    let mut parts = patch.splitn(2, '=');
+                    match (parts.next(), parts.next()) {
+                        (Some(address), Some(v)) => options
+                            .patches
+                            .push((parse_value(address)?, parse_value(v)?)),
+                        _ => {
+                            return Err(usage_error(format!(
+                                "Expected address=value, found '{}'.",
+                                patch
+                            )))
+                        }
+                    }
+                }
+            }
+            "--read" => {
+                for address in value("--read")?.split(',') {
+                    options.reads.push(parse_value(address)?);
+                }
+            }
+            "--self-modification" => options.self_modification = true,
+            "--profile" => options.profile = Some(value("--profile")?.clone()),
+            "--coverage" => options.coverage = Some(value("--coverage")?.clone()),
+            "--listen" => options.listen = Some(value("--listen")?.clone()),
+            "--connect" => options.connect = Some(value("--connect")?.clone()),
+            _ if arg.starts_with("--") => {
+                return Err(usage_error(format!("Unknown option '{}'.", arg)))
+            }
+            _ if options.path.is_none() => options.path = Some(arg.clone()),
+            _ => return Err(usage_error(format!("Unexpected argument '{}'.", arg))),
+        }
+    }
+
+    Ok(options)
+}
+
+fn load(options: &Options) -> MachineResult<Vec<i128>> {
+    Ok(load_with_names(options)?.0)
+}
+
+// Assembler listings name functions by their labels, local `.labels` left
+// out.
+fn load_with_names(options: &Options) -> MachineResult<(Vec<i128>, BTreeMap<i128, String>)> {
+    let path = options
+        .path
+        .as_ref()
+        .ok_or_else(|| usage_error("Missing program.".to_owned()))?;
+    let source = std::fs::read_to_string(path)?;
+
+    if path.ends_with(".asm") {
+        let assembly = assemble(&source)?;
+        let names = assembly
+            .labels
+            .into_iter()
+            .filter(|(name, _)| !name.starts_with('.'))
+            .map(|(name, address)| (address, name))
+            .collect();
+        Ok((assembly.program, names))
+    } else {
+        Ok((parse(&source), BTreeMap::new()))
+    }
+}
+
+// Stdout that quietly ends the program once its reader went away, like
+// `head`.
+struct Stdout;
+
+fn quiet<T>(result: std::io::Result<T>) -> std::io::Result<T> {
+    match result {
+        Err(error) if error.kind() == ErrorKind::BrokenPipe => std::process::exit(0),
+        result => result,
+    }
+}
+
+impl Write for Stdout {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        quiet(std::io::stdout().write(bytes))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        quiet(std::io::stdout().flush())
+    }
+}
+
+fn print(text: &str) -> MachineResult<()> {
+    Stdout.write_all(text.as_bytes())?;
+    Stdout.flush()?;
+    Ok(())
+}
+
+// Integers separated by commas or whitespace from the terminal, every
+// output on a line of its own.
+#[derive(Default)]
+struct NumberInterface {
+    buffer: VecDeque<i128>,
+}
+
+impl MachineInterface for NumberInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        print(&format!("{}\n", value))
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        let stdin = std::io::stdin();
+        while self.buffer.is_empty() {
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+
+            for value in line.split(|c: char| c == ',' || c.is_whitespace()) {
+                if !value.is_empty() {
+                    self.buffer.push_back(parse_value(value)?);
+                }
+            }
+        }
+
+        Ok(self.buffer.pop_front())
+    }
+}
+
+// Reads from the given values, or from the terminal if there are none, and
+// prints every output.
+struct CliInterface {
+    given: Option<VecDeque<i128>>,
+    terminal: Box<dyn MachineInterface>,
+}
+
+impl CliInterface {
+    fn new(options: &Options) -> Self {
+        let terminal: Box<dyn MachineInterface> = if options.ascii {
+            Box::new(AsciiInterface::with_io(
+                BufReader::new(std::io::stdin()),
+                Stdout,
+            ))
+        } else {
+            Box::new(NumberInterface::default())
+        };
+
+        CliInterface {
+            given: options.input.clone().map(VecDeque::from),
+            terminal,
+        }
+    }
+}
+
+impl MachineInterface for CliInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        self.terminal.send(value)
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        match &mut self.given {
+            Some(given) => Ok(given.pop_front()),
+            None => self.terminal.receive(),
+        }
+    }
+
+    fn halt(&mut self, outcome: &RunOutcome) -> MachineResult<()> {
+        self.terminal.halt(outcome)
+    }
+}
+
+fn run(options: &Options) -> MachineResult<bool> {
+    let (program, names) = load_with_names(options)?;
+    let interface: Box<dyn MachineInterface> = match (&options.listen, &options.connect) {
+        (Some(_), Some(_)) => {
+            return Err(usage_error(
+                "--listen and --connect exclude each other.".to_owned(),
+            ))
+        }
+        (Some(address), None) => Box::new(TcpInterface::listen(address, options.ascii)?),
+        (None, Some(address)) => Box::new(TcpInterface::connect(address, options.ascii)?),
+        (None, None) => Box::new(CliInterface::new(options)),
+    };
+
+    let mut executer = Executer::new(0, &program, interface, options.trace);
+    for (address, value) in &options.patches {
+        executer.set(*address, *value)?;
+    }
+    if let Some(steps) = options.max_steps {
+        executer.add_observer(Box::new(StepLimit::new(steps)));
+    }
+    let coverage = Coverage::new();
+    if options.coverage.is_some() {
+        executer.add_observer(Box::new(coverage.clone()));
+    }
+
+    let calls = CallStack::new();
+    if options.profile.is_some() {
+        executer.add_observer(Box::new(calls.clone()));
+    }
+    let detector = SelfModification::new();
+    if options.self_modification {
+        executer.add_observer(Box::new(detector.clone()));
+    }
+
+    let outcome = executer.execute();
+
+    for address in &options.reads {
+        print(&format!("{}={}\n", address, executer.get(*address)?))?;
+    }
+
+    for event in detector.events() {
+        eprintln!("{}", event);
+    }
+    if let Some(path) = &options.profile {
+        std::fs::write(path, format!("{}\n", calls.folded(&names)))?;
+    }
+
+    if let Some(path) = &options.coverage {
+        let report = coverage.report(&program);
+        std::fs::write(
+            path,
+            format!("{}\n\n{}\n", report, coverage.annotate(&program)),
+        )?;
+    }
+
+    match outcome {
+        RunOutcome::Halted => return Ok(true),
+        RunOutcome::Exhausted => eprintln!("Input exhausted at {}.", executer.counter()),
+        // Only the step limit pauses a run.
+        RunOutcome::Paused => match options.max_steps {
+            Some(steps) => eprintln!("Stopped after {} steps at {}.", steps, executer.counter()),
+            None => eprintln!("Paused at {}.", executer.counter()),
+        },
+        RunOutcome::Cancelled => eprintln!("Cancelled at {}.", executer.counter()),
+        RunOutcome::Failed(error) => return Err(error),
+    }
+    Ok(false)
+}
+
+fn execute(options: &Options) -> MachineResult<bool> {
+    match options.command.as_str() {
+        "run" => return run(options),
+        "disassemble" => print(&format!("{}\n", disassemble(&load(options)?)))?,
+        "assemble" => {
+            let path = options
+                .path
+                .as_ref()
+                .ok_or_else(|| usage_error("Missing source.".to_owned()))?;
+            let program = assemble(&std::fs::read_to_string(path)?)?.program;
+            let words: Vec<String> = program.iter().map(|v| v.to_string()).collect();
+            print(&format!("{}\n", words.join(",")))?;
+        }
+        "dap" => serve_dap_stdio()?,
+        "rpc" => match &options.listen {
+            Some(address) => serve_rpc_tcp(address)?,
+            None => serve_rpc_stdio()?,
+        },
+        "gdb" => {
+            let address = options
+                .listen
+                .as_ref()
+                .ok_or_else(|| usage_error("gdb needs --listen.".to_owned()))?;
+            let interface = CliInterface::new(options);
+            let executer = Executer::new(0, &load(options)?, Box::new(interface), false);
+            serve_gdb_tcp(&mut Debugger::new(executer), address)?;
+        }
+        "help" | "--help" | "-h" => print(&format!("{}\n", USAGE))?,
+        command => return Err(usage_error(format!("Unknown command '{}'.", command))),
+    }
+    Ok(true)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = parse_options(&args).and_then(|options| execute(&options));
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(error) => {
+            eprintln!("{} {}", error.message, error.reason);
+            if error.message == "Illegal arguments!" {
+                eprintln!("\n{}", USAGE);
+            }
+            std::process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::{Executer, OpCode, RunOutcome};
+
+// Callbacks are invoked right before the effect takes place, so the
+// executer still shows the previous counter, relative base and memory.
+pub trait Observer: Send {
+    fn instruction(&mut self, _executer: &Executer, _code: &OpCode) {}
+    fn read(&mut self, _executer: &Executer, _address: i128, _value: i128) {}
+    fn write(&mut self, _executer: &Executer, _address: i128, _value: i128) {}
+    fn relative(&mut self, _executer: &Executer, _relative: i128) {}
+    fn input(&mut self, _executer: &Executer, _value: i128) {}
+    fn output(&mut self, _executer: &Executer, _value: i128) {}
+    fn halt(&mut self, _executer: &Executer, _outcome: &RunOutcome) {}
+
+    // Asked in front of every instruction, the first one of a run included.
+    // Returning true pauses the executer there.
+    fn pause(&mut self, _executer: &Executer) -> bool {
+        false
+    }
+}
+
+// Pauses the executer once the flag is raised, usually from another
+// thread, and lowers it again.
+pub struct PauseRequest {
+    flag: Arc<AtomicBool>,
+}
+
+impl PauseRequest {
+    pub fn new(flag: Arc<AtomicBool>) -> Self {
+        PauseRequest { flag }
+    }
+}
+
+impl Observer for PauseRequest {
+    fn pause(&mut self, _executer: &Executer) -> bool {
+        self.flag.swap(false, Ordering::Relaxed)
+    }
+}
+
+// Pauses once the given number of instructions ran, or right away if that
+// number is 0.
+pub struct StepLimit {
+    remaining: u64,
+}
+
+impl StepLimit {
+    pub fn new(steps: u64) -> Self {
+        StepLimit { remaining: steps }
+    }
+}
+
+impl Observer for StepLimit {
+    fn instruction(&mut self, _executer: &Executer, _code: &OpCode) {
+        self.remaining = self.remaining.saturating_sub(1);
+    }
+
+    fn pause(&mut self, _executer: &Executer) -> bool {
+        self.remaining == 0
+    }
+}
+
+// Prints every event to stderr, away from the output of the program. This
+// is what the `debug` flag of an executer enables.
+#[cfg(feature = "std")]
+pub struct Tracer {
+    number: i128,
+}
+
+#[cfg(feature = "std")]
+impl Tracer {
+    pub fn new(number: i128) -> Self {
+        Tracer { number }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Observer for Tracer {
+    fn instruction(&mut self, executer: &Executer, code: &OpCode) {
+        let counter = executer.counter();
+        let words: Vec<i128> = (counter..counter.saturating_add(code.length().unwrap_or(1)))
+            .map(|index| executer.get(index).unwrap_or(0))
+            .collect();
+
+        eprintln!("Executer[{}]: Op: {:?} at {}", self.number, words, counter);
+    }
+
+    fn read(&mut self, _executer: &Executer, address: i128, value: i128) {
+        eprintln!(
+            "Executer[{}]:   read [{}] -> {}",
+            self.number, address, value
+        );
+    }
+
+    fn write(&mut self, _executer: &Executer, address: i128, value: i128) {
+        eprintln!(
+            "Executer[{}]:   write [{}] <- {}",
+            self.number, address, value
+        );
+    }
+
+    fn relative(&mut self, _executer: &Executer, relative: i128) {
+        eprintln!("Executer[{}]:   relative = {}", self.number, relative);
+    }
+
+    fn input(&mut self, _executer: &Executer, value: i128) {
+        eprintln!("Executer[{}]:   input -> {}", self.number, value);
+    }
+
+    fn output(&mut self, _executer: &Executer, value: i128) {
+        eprintln!("Executer[{}]:   output {}", self.number, value);
+    }
+
+    fn halt(&mut self, _executer: &Executer, outcome: &RunOutcome) {
+        eprintln!("Executer[{}]: exit {:?}", self.number, outcome);
+    }
+}
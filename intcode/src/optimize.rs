@@ -0,0 +1,517 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{
+    decode, successors, Executer, Instruction, MachineError, MachineResult, Param, QueueInterface,
+    RunOutcome, StepLimit,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rewrite {
+    // Arithmetic or a comparison on known values, replaced by storing the
+    // result.
+    Fold {
+        before: Instruction,
+        after: Instruction,
+    },
+    // A jump on a constant condition that is always taken.
+    Jump {
+        before: Instruction,
+        after: Instruction,
+    },
+    // A jump to another jump, retargeted to where the chain ends.
+    Thread {
+        before: Instruction,
+        after: Instruction,
+    },
+    // Words that are never executed or accessed, cleared to 0.
+    Clear {
+        start: i128,
+        end: i128,
+    },
+}
+
+impl std::fmt::Display for Rewrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (name, before, after) = match self {
+            Rewrite::Fold { before, after } => ("fold", before, after),
+            Rewrite::Jump { before, after } => ("jump", before, after),
+            Rewrite::Thread { before, after } => ("thread", before, after),
+            Rewrite::Clear { start, end } => {
+                return write!(f, "{:>6}  clear   {} words", start, end - start);
+            }
+        };
+
+        write!(
+            f,
+            "{:>6}  {:<6}  {} -> {}",
+            before.address, name, before, after
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Optimized {
+    pub program: Vec<i128>,
+    pub rewrites: Vec<Rewrite>,
+}
+
+// What the program may do to its own memory, as far as it can be told
+// without running it.
+struct Analysis<'a> {
+    program: &'a [i128],
+    code: BTreeMap<i128, Instruction>,
+    read: BTreeSet<i128>,
+    written: BTreeSet<i128>,
+    // Lowest address a relative access may touch.
+    stack: i128,
+}
+
+impl<'a> Analysis<'a> {
+    // Starts out assuming nothing is written and traces again with the
+    // writes found, until the traced code writes nothing new. Words that
+    // stay unwritten hold their initial value, so reads of them are
+    // constants. The written set only grows, so this ends.
+    fn new(program: &'a [i128]) -> Self {
+        let mut analysis = Analysis {
+            program,
+            code: BTreeMap::new(),
+            read: BTreeSet::new(),
+            written: BTreeSet::new(),
+            stack: i128::MAX,
+        };
+
+        loop {
+            analysis.code = analysis.trace();
+            analysis.read.clear();
+            let mut written = analysis.written.clone();
+
+            for instruction in analysis.code.values() {
+                for (index, param) in instruction.params.iter().enumerate() {
+                    if let Param::Position(address) = *param {
+                        if instruction.target() == Some(index) {
+                            written.insert(address);
+                        } else {
+                            analysis.read.insert(address);
+                        }
+                    }
+                }
+            }
+
+            let changed = written != analysis.written;
+            analysis.written = written;
+            let stack = analysis.stack_base().min(analysis.stack);
+            if !changed && stack == analysis.stack {
+                return analysis;
+            }
+            analysis.stack = stack;
+        }
+    }
+
+    fn is_written(&self, address: i128) -> bool {
+        address >= self.stack || self.written.contains(&address)
+    }
+
+    // The words can be changed without the program noticing.
+    fn is_free(&self, start: i128, length: i128) -> bool {
+        (start..start + length).all(|a| !self.is_written(a) && !self.read.contains(&a))
+    }
+
+    // Replaces reads of words that are never written by their value, and
+    // immediates that may be overwritten by a read of their word.
+    fn resolve(&self, instruction: &Instruction) -> Instruction {
+        let mut resolved = instruction.clone();
+
+        for (index, param) in resolved.params.iter_mut().enumerate() {
+            let word = instruction.address + 1 + index as i128;
+            if instruction.target() == Some(index) {
+                continue;
+            }
+
+            *param = match *param {
+                Param::Immediate(_) if self.is_written(word) => Param::Position(word),
+                Param::Position(address)
+                    if !self.is_written(address)
+                        && 0 <= address
+                        && address < self.program.len() as i128 =>
+                {
+                    Param::Immediate(self.program[address as usize])
+                }
+                param => param,
+            };
+        }
+
+        resolved
+    }
+
+    // Follows the control flow from address 0. Return addresses and
+    // function pointers are stored as constants, so every stored constant
+    // that decodes is traced as well, unless it points into the middle of
+    // known code.
+    fn trace(&self) -> BTreeMap<i128, Instruction> {
+        let mut code = BTreeMap::new();
+        let mut stored = BTreeSet::new();
+        let mut pending = vec![0];
+
+        while !pending.is_empty() {
+            while let Some(address) = pending.pop() {
+                if code.contains_key(&address) {
+                    continue;
+                }
+                let instruction = match decode(self.program, address) {
+                    Some(instruction) => instruction,
+                    None => continue,
+                };
+
+                let resolved = self.resolve(&instruction);
+                pending.extend(successors(&resolved));
+                if resolved.op == 1 || resolved.op == 2 {
+                    stored.extend(constant_result(&resolved));
+                }
+
+                code.insert(address, instruction);
+            }
+
+            let covered: BTreeSet<i128> = code
+                .values()
+                .flat_map(|i| i.address..i.address + i.length())
+                .collect();
+            pending = stored
+                .iter()
+                .copied()
+                .filter(|a| !covered.contains(a) && decode(self.program, *a).is_some())
+                .collect();
+        }
+
+        code
+    }
+
+    // The stack is taken to start at the relative base the program sets
+    // before its first relative access, less the lowest relative offset.
+    // That only holds if a single `arb` with a non-negative immediate sets
+    // the base and is reached on every path to a relative access or a
+    // computed jump. With more than one `arb` the base can move anywhere,
+    // so every address may be accessed.
+    fn stack_base(&self) -> i128 {
+        let uses_relative = |instruction: &Instruction| {
+            instruction
+                .params
+                .iter()
+                .any(|p| matches!(p, Param::Relative(_)))
+        };
+        if !self.code.values().any(uses_relative) {
+            return i128::MAX;
+        }
+        if self.code.values().filter(|i| i.op == 9).count() > 1 {
+            return i128::MIN;
+        }
+
+        let mut base = i128::MAX;
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![0];
+
+        while let Some(address) = pending.pop() {
+            if !visited.insert(address) {
+                continue;
+            }
+            let instruction = match self.code.get(&address) {
+                Some(instruction) => self.resolve(instruction),
+                None => continue,
+            };
+
+            let computed_jump =
+                is_jump(&instruction) && !matches!(instruction.params[1], Param::Immediate(_));
+            match (instruction.op, &instruction.params[..]) {
+                (9, [Param::Immediate(value)]) if *value >= 0 => base = base.min(*value),
+                _ if uses_relative(&instruction) || instruction.op == 9 || computed_jump => {
+                    return i128::MIN
+                }
+                _ => pending.extend(successors(&instruction)),
+            }
+        }
+
+        // Negative offsets reach below the base.
+        let lowest_offset = self
+            .code
+            .values()
+            .flat_map(|i| i.params.iter())
+            .filter_map(|p| match p {
+                Param::Relative(offset) => Some(*offset),
+                _ => None,
+            })
+            .min()
+            .unwrap_or(0);
+        base.saturating_add(lowest_offset.min(0))
+    }
+
+    // The traced code is only meaningful if opcodes, addresses and jump
+    // targets are never overwritten and every successor decodes. Jumps to
+    // computed targets are taken to be returns from a call.
+    fn is_consistent(&self) -> bool {
+        self.code.values().all(|instruction| {
+            let resolved = self.resolve(instruction);
+            let fixed = instruction.params.iter().enumerate().all(|(index, param)| {
+                let value = matches!(param, Param::Immediate(_))
+                    && instruction.target() != Some(index)
+                    && !(index == 1 && is_jump(instruction));
+                value || !self.is_written(instruction.address + 1 + index as i128)
+            });
+
+            fixed
+                && !self.is_written(instruction.address)
+                && successors(&resolved)
+                    .iter()
+                    .all(|next| self.code.contains_key(next))
+        })
+    }
+}
+
+fn is_jump(instruction: &Instruction) -> bool {
+    instruction.op == 5 || instruction.op == 6
+}
+
+fn constant_result(instruction: &Instruction) -> Option<i128> {
+    let value = |index: usize| match instruction.params.get(index) {
+        Some(Param::Immediate(value)) => Some(*value),
+        _ => None,
+    };
+
+    match instruction.op {
+        // An overflow is left to fail at run time.
+        1 => value(0)?.checked_add(value(1)?),
+        2 => match (value(0), value(1)) {
+            (Some(0), _) | (_, Some(0)) => Some(0),
+            (a, b) => a?.checked_mul(b?),
+        },
+        7 | 8 => match &instruction.params[..] {
+            [a, b, _] if a == b => Some((instruction.op == 8) as i128),
+            _ if instruction.op == 7 => Some((value(0)? < value(1)?) as i128),
+            _ => Some((value(0)? == value(1)?) as i128),
+        },
+        _ => None,
+    }
+}
+
+fn fold(instruction: &Instruction) -> Option<Instruction> {
+    Some(Instruction {
+        address: instruction.address,
+        op: 1,
+        params: vec![
+            Param::Immediate(constant_result(instruction)?),
+            Param::Immediate(0),
+            instruction.params[2],
+        ],
+    })
+}
+
+// An `add` or `mul` that already stores an immediate, folding it would
+// only reorder the operands.
+fn stores_constant(instruction: &Instruction) -> bool {
+    match (instruction.op, &instruction.params[..]) {
+        (1, [Param::Immediate(a), Param::Immediate(b), _]) => *a == 0 || *b == 0,
+        (2, [Param::Immediate(a), Param::Immediate(b), _]) => *a == 1 || *b == 1,
+        _ => false,
+    }
+}
+
+// `Some(true)` for a jump that is always taken, `Some(false)` for one that
+// never is.
+fn constant_jump(instruction: &Instruction) -> Option<bool> {
+    match (instruction.op, &instruction.params[..]) {
+        (5, [Param::Immediate(c), _]) => Some(*c != 0),
+        (6, [Param::Immediate(c), _]) => Some(*c == 0),
+        _ => None,
+    }
+}
+
+fn unconditional(instruction: &Instruction) -> Option<Instruction> {
+    if constant_jump(instruction) != Some(true) {
+        return None;
+    }
+
+    Some(Instruction {
+        address: instruction.address,
+        op: 5,
+        params: vec![Param::Immediate(1), instruction.params[1]],
+    })
+}
+
+// Expects the jump resolved.
+fn thread(analysis: &Analysis, program: &[i128], instruction: &Instruction) -> Option<Instruction> {
+    if constant_jump(instruction) == Some(false) {
+        return None;
+    }
+    let mut target = match instruction.params[1] {
+        Param::Immediate(target) => target,
+        _ => return None,
+    };
+
+    let mut visited = BTreeSet::new();
+    while visited.insert(target) {
+        let next = match decode(program, target) {
+            Some(next) if analysis.code.contains_key(&target) => analysis.resolve(&next),
+            _ => break,
+        };
+
+        target = match (constant_jump(&next), next.params.get(1)) {
+            (Some(true), Some(Param::Immediate(next_target))) => *next_target,
+            (Some(false), _) => target + next.length(),
+            _ => break,
+        };
+    }
+
+    if Param::Immediate(target) == instruction.params[1] {
+        return None;
+    }
+
+    let mut after = instruction.clone();
+    after.params[1] = Param::Immediate(target);
+    Some(after)
+}
+
+fn write(program: &mut [i128], instruction: &Instruction) {
+    for (index, word) in instruction.encode().into_iter().enumerate() {
+        program[instruction.address as usize + index] = word;
+    }
+}
+
+// Rewrites the program in place, so every address keeps its meaning. Only
+// instructions the program never writes or reads are changed; if a write
+// could change an opcode, an address or a jump target the program is
+// returned as is.
+pub fn optimize(program: &[i128]) -> Optimized {
+    let analysis = Analysis::new(program);
+    let mut result = program.to_owned();
+    let mut rewrites = Vec::new();
+
+    if !analysis.is_consistent() {
+        return Optimized {
+            program: result,
+            rewrites,
+        };
+    }
+
+    for instruction in analysis.code.values() {
+        if !analysis.is_free(instruction.address, instruction.length()) {
+            continue;
+        }
+
+        let resolved = analysis.resolve(instruction);
+        let before = instruction.clone();
+
+        if is_jump(instruction) {
+            // A jump on an immediate is already as cheap as it gets.
+            if matches!(instruction.params[0], Param::Immediate(_)) {
+                continue;
+            }
+            if let Some(after) = unconditional(&resolved) {
+                write(&mut result, &after);
+                rewrites.push(Rewrite::Jump { before, after });
+            }
+        } else if !stores_constant(instruction) {
+            if let Some(after) = fold(&resolved) {
+                write(&mut result, &after);
+                rewrites.push(Rewrite::Fold { before, after });
+            }
+        }
+    }
+
+    // Threading looks at the already rewritten jumps.
+    for address in analysis.code.keys() {
+        let instruction = match decode(&result, *address) {
+            Some(instruction) if is_jump(&instruction) => instruction,
+            _ => continue,
+        };
+        if !analysis.is_free(instruction.address, instruction.length()) {
+            continue;
+        }
+
+        if let Some(after) = thread(&analysis, &result, &analysis.resolve(&instruction)) {
+            write(&mut result, &after);
+            rewrites.push(Rewrite::Thread {
+                before: instruction,
+                after,
+            });
+        }
+    }
+
+    let mut used = vec![false; program.len()];
+    for instruction in analysis.code.values() {
+        for address in instruction.address..instruction.address + instruction.length() {
+            used[address as usize] = true;
+        }
+    }
+
+    let mut address = 0;
+    while address < program.len() as i128 {
+        let clear = |a: i128| !used[a as usize] && analysis.is_free(a, 1);
+        if !clear(address) {
+            address += 1;
+            continue;
+        }
+
+        let start = address;
+        while address < program.len() as i128 && clear(address) {
+            address += 1;
+        }
+
+        let words = &mut result[start as usize..address as usize];
+        if words.iter().any(|w| *w != 0) {
+            words.iter_mut().for_each(|w| *w = 0);
+            rewrites.push(Rewrite::Clear {
+                start,
+                end: address,
+            });
+        }
+    }
+
+    Optimized {
+        program: result,
+        rewrites,
+    }
+}
+
+fn run_with_inputs(
+    program: &[i128],
+    inputs: &[i128],
+    max_steps: u64,
+) -> MachineResult<(RunOutcome, Vec<i128>)> {
+    let interface = QueueInterface::new();
+    interface.push_input(inputs);
+
+    let mut executer = Executer::new(0, program, Box::new(interface.clone()), false);
+    executer.add_observer(Box::new(StepLimit::new(max_steps)));
+    match executer.execute() {
+        RunOutcome::Paused => Err(MachineError {
+            message: "Run stopped!".to_owned(),
+            reason: format!("The program did not halt within {} steps.", max_steps),
+        }),
+        outcome => Ok((outcome, interface.take_output())),
+    }
+}
+
+// Runs both programs on the same inputs and returns the outputs, or an
+// error if the runs differ in their outputs or in how they ended, or if
+// either one runs more than `max_steps` instructions.
+pub fn verify_optimization(
+    program: &[i128],
+    optimized: &[i128],
+    inputs: &[i128],
+    max_steps: u64,
+) -> MachineResult<Vec<i128>> {
+    let (outcome, outputs) = run_with_inputs(program, inputs, max_steps)?;
+    let (optimized_outcome, optimized_outputs) = run_with_inputs(optimized, inputs, max_steps)?;
+
+    let outcome = format!("{:?}", outcome);
+    let optimized_outcome = format!("{:?}", optimized_outcome);
+    if outputs != optimized_outputs || outcome != optimized_outcome {
+        return Err(MachineError {
+            message: "Optimization changed behaviour!".to_owned(),
+            reason: format!(
+                "Original: {} with outputs {:?}, optimized: {} with outputs {:?}.",
+                outcome, outputs, optimized_outcome, optimized_outputs
+            ),
+        });
+    }
+
+    Ok(outputs)
+}
@@ -0,0 +1,190 @@
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::{Executer, MachineError, MachineResult, QueueInterface, RunOutcome, StepLimit};
+
+#[derive(Debug, Clone)]
+pub struct PatchResult {
+    // The cells passed to `read`, in the same order.
+    pub values: Vec<i128>,
+    pub output: Vec<i128>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchMatch {
+    pub patches: Vec<(i128, i128)>,
+    pub result: PatchResult,
+}
+
+// Runs the program with some memory cells replaced before the first
+// instruction, like the noun and verb of day 2, and reads cells back once
+// it halted.
+#[derive(Debug, Clone)]
+pub struct PatchedRun {
+    program: Vec<i128>,
+    patches: Vec<(i128, i128)>,
+    input: Vec<i128>,
+    reads: Vec<i128>,
+    max_steps: Option<u64>,
+}
+
+impl PatchedRun {
+    pub fn new(program: &[i128]) -> Self {
+        PatchedRun {
+            program: program.to_owned(),
+            patches: Vec::new(),
+            input: Vec::new(),
+            reads: Vec::new(),
+            max_steps: None,
+        }
+    }
+
+    pub fn patch(mut self, address: i128, value: i128) -> Self {
+        self.patches.push((address, value));
+        self
+    }
+
+    pub fn input(mut self, values: &[i128]) -> Self {
+        self.input.extend(values);
+        self
+    }
+
+    pub fn read(mut self, addresses: &[i128]) -> Self {
+        self.reads.extend(addresses);
+        self
+    }
+
+    // Stops a run after this many instructions, a program that loops
+    // forever is not a result.
+    pub fn max_steps(mut self, steps: u64) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    pub fn run(&self) -> MachineResult<PatchResult> {
+        match self.execute(&[], None)? {
+            Some(result) => Ok(result),
+            None => Err(MachineError {
+                message: "Run stopped!".to_owned(),
+                reason: match self.max_steps {
+                    Some(steps) => format!("The program did not halt within {} steps.", steps),
+                    None => "The program did not halt.".to_owned(),
+                },
+            }),
+        }
+    }
+
+    // `None` if the run was cancelled or ran out of steps.
+    fn execute(
+        &self,
+        patches: &[(i128, i128)],
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> MachineResult<Option<PatchResult>> {
+        let interface = QueueInterface::new();
+        interface.push_input(&self.input);
+
+        let mut executer = Executer::new(0, &self.program, Box::new(interface.clone()), false);
+        for (address, value) in self.patches.iter().chain(patches) {
+            executer.set(*address, *value)?;
+        }
+        if let Some(cancel) = cancel {
+            executer.set_cancel_flag(cancel);
+        }
+        if let Some(steps) = self.max_steps {
+            executer.add_observer(Box::new(StepLimit::new(steps)));
+        }
+
+        match executer.execute() {
+            RunOutcome::Halted => {}
+            RunOutcome::Exhausted => {
+                return Err(MachineError {
+                    message: "Input exhausted!".to_owned(),
+                    reason: format!(
+                        "The program needs more than the {} given values.",
+                        self.input.len()
+                    ),
+                })
+            }
+            RunOutcome::Paused | RunOutcome::Cancelled => return Ok(None),
+            RunOutcome::Failed(error) => return Err(error),
+        }
+
+        let values = self
+            .reads
+            .iter()
+            .map(|address| executer.get(*address))
+            .collect::<MachineResult<_>>()?;
+        Ok(Some(PatchResult {
+            values,
+            output: interface.take_output(),
+        }))
+    }
+
+    // Tries every combination of the grid on top of the run's own patches,
+    // each grid entry being an address and the values it takes, with the
+    // last entry changing fastest. Runs that fail or exceed `max_steps` are
+    // skipped. Once one satisfies `goal` the remaining runs are cancelled,
+    // so with several matches it is not defined which one is returned.
+    pub fn search<G>(
+        &self,
+        grid: &[(i128, RangeInclusive<i128>)],
+        threads: usize,
+        goal: G,
+    ) -> MachineResult<Option<PatchMatch>>
+    where
+        G: Fn(&PatchResult) -> bool + Sync,
+    {
+        let too_large = || MachineError {
+            message: "Grid too large!".to_owned(),
+            reason: "The number of combinations does not fit into 64 bits.".to_owned(),
+        };
+        let sizes = grid
+            .iter()
+            .map(|(_, values)| {
+                if values.is_empty() {
+                    return Ok(0);
+                }
+                let span = values.end().checked_sub(*values.start());
+                span.and_then(|span| u64::try_from(span).ok())
+                    .and_then(|span| span.checked_add(1))
+                    .ok_or_else(too_large)
+            })
+            .collect::<MachineResult<Vec<u64>>>()?;
+        let total = sizes
+            .iter()
+            .try_fold(1u64, |total, size| total.checked_mul(*size))
+            .ok_or_else(too_large)?;
+
+        let next = AtomicU64::new(0);
+        let found = Arc::new(AtomicBool::new(false));
+        let matched = Mutex::new(None);
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| loop {
+                    let mut index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= total || found.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    let mut patches = vec![(0, 0); grid.len()];
+                    for (entry, (address, values)) in grid.iter().enumerate().rev() {
+                        let offset = index % sizes[entry];
+                        index /= sizes[entry];
+                        patches[entry] = (*address, values.start() + offset as i128);
+                    }
+
+                    if let Ok(Some(result)) = self.execute(&patches, Some(found.clone())) {
+                        if goal(&result) && !found.swap(true, Ordering::Relaxed) {
+                            *matched.lock().unwrap() = Some(PatchMatch { patches, result });
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(matched.into_inner().unwrap())
+    }
+}
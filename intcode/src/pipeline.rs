@@ -0,0 +1,82 @@
+use super::{MachineError, MachineInterface, MachineResult, Topology};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineMode {
+    // The output of the last stage is collected and not passed on.
+    Linear,
+    // The output of the last stage is fed back into the first stage.
+    Feedback,
+}
+
+#[derive(Debug)]
+pub struct Pipeline {
+    program: Vec<i128>,
+    mode: PipelineMode,
+    stages: Vec<Vec<i128>>,
+}
+
+impl Pipeline {
+    pub fn new(program: &[i128], mode: PipelineMode) -> Self {
+        Pipeline {
+            program: program.to_owned(),
+            mode,
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn stage(mut self, initial_inputs: &[i128]) -> Self {
+        self.stages.push(initial_inputs.to_owned());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn run(&self, stage: usize) -> MachineResult<i128> {
+        self.run_with(stage, |_, interface| Ok(interface))
+    }
+
+    // Like `run`, `wrap` gets the interface of every stage before it starts.
+    pub fn run_with<F>(&self, stage: usize, wrap: F) -> MachineResult<i128>
+    where
+        F: FnMut(usize, Box<dyn MachineInterface>) -> MachineResult<Box<dyn MachineInterface>>,
+    {
+        if stage >= self.stages.len() {
+            return Err(MachineError {
+                message: "Unknown pipeline stage!".to_owned(),
+                reason: format!(
+                    "Requested stage {}, but pipeline has {} stages.",
+                    stage,
+                    self.stages.len()
+                ),
+            });
+        }
+
+        let mut topology = Topology::new();
+        for (index, inputs) in self.stages.iter().enumerate() {
+            topology = topology.node(&index.to_string(), inputs)?;
+        }
+
+        // Stage `i` writes into the input of stage `i + 1`. The last stage
+        // either closes the ring or its output is only collected.
+        let count = self.stages.len();
+        for index in 1..count {
+            topology.edges.push((index - 1, index));
+        }
+        if self.mode == PipelineMode::Feedback {
+            topology.edges.push((count - 1, 0));
+        }
+
+        let last_outputs = topology.run_with(&self.program, 0, wrap)?;
+
+        last_outputs[stage].ok_or_else(|| MachineError {
+            message: "No pipeline output!".to_owned(),
+            reason: format!("Stage {} halted without producing any output.", stage),
+        })
+    }
+}
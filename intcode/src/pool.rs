@@ -0,0 +1,108 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use super::{MachineError, RunOutcome};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// A fixed number of worker threads that pick up jobs in submission order.
+// Dropping the pool waits for all queued jobs to finish.
+#[derive(Debug)]
+pub struct WorkerPool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let (sender, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+
+                std::thread::spawn(move || loop {
+                    let job = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => break,
+                    };
+
+                    // A panicking job must not take the worker down with it.
+                    // Its handle sees the dropped sender and reports a failure.
+                    match job {
+                        Ok(job) => {
+                            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                        }
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+
+        WorkerPool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        if let Some(sender) = &self.sender {
+            // Workers only stop after the sender is dropped, so this cannot fail.
+            let _ = sender.send(Box::new(job));
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.sender.take();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MachineHandle {
+    number: i128,
+    cancel: Arc<AtomicBool>,
+    outcome: Receiver<RunOutcome>,
+}
+
+impl MachineHandle {
+    pub fn new(number: i128, cancel: Arc<AtomicBool>, outcome: Receiver<RunOutcome>) -> Self {
+        MachineHandle {
+            number,
+            cancel,
+            outcome,
+        }
+    }
+
+    pub fn number(&self) -> i128 {
+        self.number
+    }
+
+    // Stops the executer before its next instruction. A machine that is
+    // blocked on input only notices once the input arrives.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn join(self) -> RunOutcome {
+        self.outcome.recv().unwrap_or_else(|_| {
+            RunOutcome::Failed(MachineError {
+                message: "Executer panicked!".to_owned(),
+                reason: format!("Executer {} stopped without an outcome.", self.number),
+            })
+        })
+    }
+}
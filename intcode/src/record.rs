@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use super::{MachineError, MachineInterface, MachineResult, RunOutcome};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    Input(i128),
+    Output(i128),
+    // The interface had no more input for the program.
+    Exhausted,
+}
+
+impl TranscriptEvent {
+    pub fn parse(line: &str) -> MachineResult<Self> {
+        let mut parts = line.split_whitespace();
+        let kind = parts.next();
+        let value = parts.next().and_then(|v| v.parse::<i128>().ok());
+
+        match (kind, value, parts.next()) {
+            (Some("in"), Some(value), None) => Ok(TranscriptEvent::Input(value)),
+            (Some("out"), Some(value), None) => Ok(TranscriptEvent::Output(value)),
+            (Some("eof"), None, None) => Ok(TranscriptEvent::Exhausted),
+            _ => Err(MachineError {
+                message: "Illegal transcript line!".to_owned(),
+                reason: format!(
+                    "Cannot parse '{}', expected 'in <value>', 'out <value>' or 'eof'.",
+                    line
+                ),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for TranscriptEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TranscriptEvent::Input(value) => write!(f, "in {}", value),
+            TranscriptEvent::Output(value) => write!(f, "out {}", value),
+            TranscriptEvent::Exhausted => write!(f, "eof"),
+        }
+    }
+}
+
+pub fn parse_transcript(data: &str) -> MachineResult<Vec<TranscriptEvent>> {
+    data.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(TranscriptEvent::parse)
+        .collect()
+}
+
+pub struct RecordInterface {
+    inner: Box<dyn MachineInterface>,
+    writer: BufWriter<File>,
+}
+
+impl RecordInterface {
+    pub fn new(inner: Box<dyn MachineInterface>, path: &str) -> MachineResult<Self> {
+        Ok(RecordInterface {
+            inner,
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    fn write(&mut self, event: TranscriptEvent) -> MachineResult<()> {
+        // Flush every event, so the transcript survives a failing run.
+        writeln!(self.writer, "{}", event)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl MachineInterface for RecordInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        self.write(TranscriptEvent::Output(value))?;
+        self.inner.send(value)
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        let value = self.inner.receive()?;
+        self.write(match value {
+            Some(value) => TranscriptEvent::Input(value),
+            None => TranscriptEvent::Exhausted,
+        })?;
+        Ok(value)
+    }
+
+    fn halt(&mut self, outcome: &RunOutcome) -> MachineResult<()> {
+        self.inner.halt(outcome)
+    }
+}
+
+pub struct ReplayInterface {
+    events: Vec<TranscriptEvent>,
+    position: usize,
+}
+
+impl ReplayInterface {
+    pub fn new(events: Vec<TranscriptEvent>) -> Self {
+        ReplayInterface {
+            events,
+            position: 0,
+        }
+    }
+
+    pub fn from_file(path: &str) -> MachineResult<Self> {
+        Ok(ReplayInterface::new(parse_transcript(
+            &std::fs::read_to_string(path)?,
+        )?))
+    }
+
+    fn next(&mut self, actual: &str) -> MachineResult<TranscriptEvent> {
+        match self.events.get(self.position) {
+            Some(expected) => {
+                self.position += 1;
+                Ok(*expected)
+            }
+            None => Err(MachineError {
+                message: "Replay mismatch!".to_owned(),
+                reason: format!(
+                    "Transcript ended after {} events, but program wants {}.",
+                    self.events.len(),
+                    actual
+                ),
+            }),
+        }
+    }
+
+    fn mismatch(&self, expected: TranscriptEvent, actual: &str) -> MachineError {
+        MachineError {
+            message: "Replay mismatch!".to_owned(),
+            reason: format!(
+                "Expected '{}' at event {}, but program wants {}.",
+                expected, self.position, actual
+            ),
+        }
+    }
+}
+
+impl MachineInterface for ReplayInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        let actual = format!("'{}'", TranscriptEvent::Output(value));
+
+        match self.next(&actual)? {
+            TranscriptEvent::Output(expected) if expected == value => Ok(()),
+            expected => Err(self.mismatch(expected, &actual)),
+        }
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        match self.next("input")? {
+            TranscriptEvent::Input(value) => Ok(Some(value)),
+            TranscriptEvent::Exhausted => Ok(None),
+            expected => Err(self.mismatch(expected, "input")),
+        }
+    }
+
+    // A faithful replay has used up the whole transcript when the program stops.
+    fn halt(&mut self, _outcome: &RunOutcome) -> MachineResult<()> {
+        match self.events.get(self.position) {
+            None => Ok(()),
+            Some(expected) => Err(MachineError {
+                message: "Replay mismatch!".to_owned(),
+                reason: format!(
+                    "Program stopped after {} of {} events, but transcript expects '{}'.",
+                    self.position,
+                    self.events.len(),
+                    expected
+                ),
+            }),
+        }
+    }
+}
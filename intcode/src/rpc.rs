@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+
+use super::{
+    parse, parse_file, Executer, Json, MachineError, MachineResult, QueueInterface, RunOutcome,
+};
+
+const PARSE_ERROR: i128 = -32700;
+const INVALID_REQUEST: i128 = -32600;
+const METHOD_NOT_FOUND: i128 = -32601;
+const INVALID_PARAMS: i128 = -32602;
+// Errors reported by the machine itself.
+const MACHINE_ERROR: i128 = -32000;
+// A `run` without `maxSteps` pauses after this many instructions, so a
+// program that never halts does not block the connection.
+const DEFAULT_MAX_STEPS: i128 = 10_000_000;
+// Words returned by one `inspectMemory` at most.
+const MAX_INSPECT: i128 = 100_000;
+
+struct RpcError {
+    code: i128,
+    message: String,
+}
+
+impl From<MachineError> for RpcError {
+    fn from(error: MachineError) -> Self {
+        RpcError {
+            code: MACHINE_ERROR,
+            message: format!("{} {}", error.message, error.reason),
+        }
+    }
+}
+
+fn invalid_params(message: &str) -> RpcError {
+    RpcError {
+        code: INVALID_PARAMS,
+        message: message.to_owned(),
+    }
+}
+
+type RpcResult = Result<Json, RpcError>;
+
+struct RpcMachine {
+    executer: Executer,
+    interface: QueueInterface,
+}
+
+fn outcome_name(outcome: &RunOutcome) -> &'static str {
+    match outcome {
+        RunOutcome::Halted => "halted",
+        RunOutcome::Exhausted => "exhausted",
+        RunOutcome::Paused => "paused",
+        RunOutcome::Cancelled => "cancelled",
+        RunOutcome::Failed(_) => "failed",
+    }
+}
+
+// The machines of one connection, addressed by the id `load` returns.
+#[derive(Default)]
+struct RpcServer {
+    machines: BTreeMap<i128, RpcMachine>,
+    next: i128,
+    // Whether `load` may read a program from a local file.
+    files: bool,
+}
+
+impl RpcServer {
+    fn machine(&mut self, params: &Json) -> Result<&mut RpcMachine, RpcError> {
+        let id = params
+            .get("machine")
+            .as_i128()
+            .ok_or_else(|| invalid_params("Missing 'machine'."))?;
+        self.machines
+            .get_mut(&id)
+            .ok_or_else(|| invalid_params(&format!("Unknown machine {}.", id)))
+    }
+
+    fn load(&mut self, params: &Json) -> RpcResult {
+        let program = match (params.get("program"), params.get("path").as_str()) {
+            (Json::Array(values), _) => values
+                .iter()
+                .map(|v| {
+                    v.as_i128()
+                        .ok_or_else(|| invalid_params("Illegal program."))
+                })
+                .collect::<Result<Vec<i128>, RpcError>>()?,
+            (Json::String(text), _) => parse(text),
+            (_, Some(path)) if self.files => parse_file(path)?,
+            (_, Some(_)) => return Err(invalid_params("Loading by 'path' is not allowed.")),
+            _ => return Err(invalid_params("Missing 'program' or 'path'.")),
+        };
+
+        let interface = QueueInterface::new();
+        let id = self.next;
+        self.next += 1;
+        self.machines.insert(
+            id,
+            RpcMachine {
+                executer: Executer::new(id, &program, Box::new(interface.clone()), false),
+                interface,
+            },
+        );
+
+        Ok(Json::object(vec![
+            ("machine", Json::from(id)),
+            ("size", Json::from(program.len() as i128)),
+        ]))
+    }
+
+    // Runs until the program stops, or at most `maxSteps` instructions.
+    fn run(&mut self, params: &Json, single: bool) -> RpcResult {
+        let limit = if single {
+            params.get("count").as_i128().unwrap_or(1)
+        } else {
+            params
+                .get("maxSteps")
+                .as_i128()
+                .unwrap_or(DEFAULT_MAX_STEPS)
+        };
+        let machine = self.machine(params)?;
+
+        let mut steps = 0;
+        let mut outcome = RunOutcome::Paused;
+        while steps < limit {
+            outcome = machine.executer.step();
+            steps += 1;
+            if !matches!(outcome, RunOutcome::Paused) {
+                break;
+            }
+        }
+
+        let mut fields = vec![
+            ("outcome", Json::from(outcome_name(&outcome))),
+            ("counter", Json::from(machine.executer.counter())),
+            ("relative", Json::from(machine.executer.relative())),
+        ];
+        if let RunOutcome::Failed(error) = outcome {
+            fields.push((
+                "error",
+                Json::from(format!("{} {}", error.message, error.reason)),
+            ));
+        }
+        Ok(Json::object(fields))
+    }
+
+    fn call(&mut self, method: &str, params: &Json) -> RpcResult {
+        match method {
+            "load" => self.load(params),
+            "unload" => {
+                self.machine(params)?;
+                let id = params.get("machine").as_i128().unwrap_or(0);
+                self.machines.remove(&id);
+                Ok(Json::Null)
+            }
+            "run" => self.run(params, false),
+            "step" => self.run(params, true),
+            // `values` are sent as they are, `text` as ASCII.
+            "sendInput" => {
+                let machine = self.machine(params)?;
+                let input: Vec<i128> = match params.get("text").as_str() {
+                    Some(text) => text.chars().map(|c| c as i128).collect(),
+                    None => params
+                        .get("values")
+                        .as_array()
+                        .unwrap_or(&[])
+                        .iter()
+                        .map(|v| v.as_i128().ok_or_else(|| invalid_params("Illegal value.")))
+                        .collect::<Result<_, _>>()?,
+                };
+                machine.interface.push_input(&input);
+                Ok(Json::object(vec![(
+                    "pending",
+                    Json::from(machine.interface.pending_input() as i128),
+                )]))
+            }
+            "readOutput" => {
+                let machine = self.machine(params)?;
+                let output = machine.interface.take_output();
+                Ok(Json::object(vec![(
+                    "values",
+                    Json::from(output.into_iter().map(Json::from).collect::<Vec<Json>>()),
+                )]))
+            }
+            "snapshot" => {
+                let machine = self.machine(params)?;
+                let executer = &machine.executer;
+                let extra = executer
+                    .extra_memory()
+                    .into_iter()
+                    .map(|(address, value)| {
+                        Json::from(vec![Json::from(address), Json::from(value)])
+                    })
+                    .collect::<Vec<Json>>();
+
+                Ok(Json::object(vec![
+                    ("counter", Json::from(executer.counter())),
+                    ("relative", Json::from(executer.relative())),
+                    ("finished", Json::from(executer.is_finished())),
+                    (
+                        "memory",
+                        Json::from(
+                            executer
+                                .memory()
+                                .iter()
+                                .map(|v| Json::from(*v))
+                                .collect::<Vec<Json>>(),
+                        ),
+                    ),
+                    ("extra", Json::from(extra)),
+                    (
+                        "pendingInput",
+                        Json::from(machine.interface.pending_input() as i128),
+                    ),
+                ]))
+            }
+            "inspectMemory" => {
+                let address = params
+                    .get("address")
+                    .as_i128()
+                    .ok_or_else(|| invalid_params("Missing 'address'."))?;
+                let count = params.get("count").as_i128().unwrap_or(1);
+                if count > MAX_INSPECT {
+                    return Err(invalid_params(&format!(
+                        "At most {} words can be inspected at once.",
+                        MAX_INSPECT
+                    )));
+                }
+                let end = address
+                    .checked_add(count.max(0))
+                    .ok_or_else(|| invalid_params("The words leave the address space."))?;
+                let machine = self.machine(params)?;
+
+                let values = (address..end)
+                    .map(|a| machine.executer.get(a).map(Json::from))
+                    .collect::<Result<Vec<Json>, MachineError>>()?;
+                Ok(Json::object(vec![("values", Json::from(values))]))
+            }
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unknown method '{}'.", method),
+            }),
+        }
+    }
+
+    // The response to one request, `None` for notifications.
+    fn handle(&mut self, request: &Json) -> Option<Json> {
+        let id = request.get("id").clone();
+        let result = match request.get("method").as_str() {
+            Some(method) if request.get("jsonrpc").as_str() == Some("2.0") => {
+                self.call(method, request.get("params"))
+            }
+            _ => Err(RpcError {
+                code: INVALID_REQUEST,
+                message: "Expected a JSON-RPC 2.0 request.".to_owned(),
+            }),
+        };
+
+        if id.is_null() && request.get("method").as_str().is_some() {
+            return None;
+        }
+        Some(response(id, result))
+    }
+}
+
+fn response(id: Json, result: RpcResult) -> Json {
+    let outcome = match result {
+        Ok(value) => ("result", value),
+        Err(error) => (
+            "error",
+            Json::object(vec![
+                ("code", Json::from(error.code)),
+                ("message", Json::from(error.message)),
+            ]),
+        ),
+    };
+    Json::object(vec![("jsonrpc", Json::from("2.0")), outcome, ("id", id)])
+}
+
+// Serves JSON-RPC 2.0, one request or batch per line and one response per
+// line. Methods, all taking named parameters:
+//
+// - `load` with `program` as an array or comma separated string, or `path`
+//   if `files` is set, gives the `machine` id the other methods take
+// - `run`, optionally with `maxSteps`, and `step` with `count`; a run stops
+//   as `paused` after ten million instructions unless told otherwise
+// - `sendInput` with `values` or ASCII `text`, `readOutput`
+// - `snapshot`, `inspectMemory` with `address` and up to 100000 words in
+//   `count`, `unload`
+//
+// A program that needs more input stops as `exhausted` and continues with
+// the next `run` once input was sent.
+pub fn serve_rpc<R: BufRead, W: Write>(reader: R, mut writer: W, files: bool) -> MachineResult<()> {
+    let mut server = RpcServer {
+        files,
+        ..RpcServer::default()
+    };
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match Json::parse(&line) {
+            Ok(Json::Array(requests)) if !requests.is_empty() => {
+                let replies: Vec<Json> = requests.iter().filter_map(|r| server.handle(r)).collect();
+                if replies.is_empty() {
+                    continue;
+                }
+                Json::from(replies)
+            }
+            Ok(request) => match server.handle(&request) {
+                Some(reply) => reply,
+                None => continue,
+            },
+            Err(error) => response(
+                Json::Null,
+                Err(RpcError {
+                    code: PARSE_ERROR,
+                    message: format!("{} {}", error.message, error.reason),
+                }),
+            ),
+        };
+
+        writeln!(writer, "{}", reply)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+pub fn serve_rpc_stdio() -> MachineResult<()> {
+    let stdin = std::io::stdin();
+    serve_rpc(stdin.lock(), std::io::stdout(), true)
+}
+
+// Accepts clients on `address` until the listener fails, each one on its own
+// thread and with its own machines. Remote clients cannot load by `path`,
+// that would let them read any file of the server.
+pub fn serve_rpc_tcp(address: &str) -> MachineResult<()> {
+    let listener = std::net::TcpListener::bind(address)?;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        std::thread::spawn(move || {
+            if let Ok(reader) = stream.try_clone() {
+                let _ = serve_rpc(BufReader::new(reader), &stream, false);
+            }
+        });
+    }
+    Ok(())
+}
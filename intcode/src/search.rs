@@ -0,0 +1,143 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+
+use super::{Executer, MachineResult, QueueInterface, RunOutcome, StepLimit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOrder {
+    // Finds the shortest input sequence.
+    BreadthFirst,
+    // Finds some input sequence, usually with less memory.
+    DepthFirst,
+}
+
+// A move gets this many instructions to reach the next input.
+const DEFAULT_MAX_STEPS: u64 = 1_000_000;
+
+// Runs a fork of `executer` with the inputs until it needs the next one.
+// `None` if it is still running once the step budget is spent.
+fn step(
+    executer: &Executer,
+    inputs: &[i128],
+    max_steps: u64,
+) -> MachineResult<Option<(Executer, Vec<i128>)>> {
+    let interface = QueueInterface::new();
+    interface.push_input(inputs);
+
+    let mut fork = executer.fork(Box::new(interface.clone()));
+    fork.add_observer(Box::new(StepLimit::new(max_steps)));
+
+    match fork.execute() {
+        RunOutcome::Failed(error) => Err(error),
+        RunOutcome::Paused | RunOutcome::Cancelled => Ok(None),
+        RunOutcome::Halted | RunOutcome::Exhausted => Ok(Some((fork, interface.take_output()))),
+    }
+}
+
+pub struct SearchResult<S> {
+    pub inputs: Vec<i128>,
+    pub state: S,
+    // Stopped in front of the next input, ready to continue from the goal
+    // once it got an interface of its own with `fork`. It has no step
+    // limit.
+    pub executer: Executer,
+    pub explored: usize,
+}
+
+pub struct Search {
+    program: Vec<i128>,
+    moves: Vec<i128>,
+    order: SearchOrder,
+    max_steps: u64,
+}
+
+impl Search {
+    pub fn new(program: &[i128], moves: &[i128]) -> Self {
+        Search {
+            program: program.to_owned(),
+            moves: moves.to_owned(),
+            order: SearchOrder::BreadthFirst,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    pub fn order(mut self, order: SearchOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    // A move that needs more instructions to reach the next input is
+    // treated like one that leads nowhere.
+    pub fn max_steps(mut self, steps: u64) -> Self {
+        self.max_steps = steps;
+        self
+    }
+
+    // Tries every move from every reachable state. `read` gets the current
+    // state, the move and the outputs it produced and returns the new state,
+    // or `None` if the move leads nowhere. States are only visited once.
+    pub fn run<S, R, G>(
+        &self,
+        start: S,
+        mut read: R,
+        mut goal: G,
+    ) -> MachineResult<Option<SearchResult<S>>>
+    where
+        S: Clone + Eq + Hash,
+        R: FnMut(&S, i128, &[i128]) -> Option<S>,
+        G: FnMut(&S) -> bool,
+    {
+        let root = Executer::new(0, &self.program, Box::new(QueueInterface::new()), false);
+        let root = match step(&root, &[], self.max_steps)? {
+            Some((root, _)) => root,
+            None => return Ok(None),
+        };
+
+        let mut visited = HashSet::new();
+        visited.insert(start.clone());
+
+        let mut explored = 0;
+        let mut queue = VecDeque::new();
+        queue.push_back((root, start, Vec::new()));
+
+        loop {
+            let next = match self.order {
+                SearchOrder::BreadthFirst => queue.pop_front(),
+                SearchOrder::DepthFirst => queue.pop_back(),
+            };
+            let (executer, state, inputs) = match next {
+                Some(node) => node,
+                None => return Ok(None),
+            };
+
+            explored += 1;
+            if goal(&state) {
+                return Ok(Some(SearchResult {
+                    inputs,
+                    state,
+                    executer: executer.fork(Box::new(QueueInterface::new())),
+                    explored,
+                }));
+            }
+
+            if executer.is_finished() {
+                continue;
+            }
+
+            for value in &self.moves {
+                let (fork, outputs) = match step(&executer, &[*value], self.max_steps)? {
+                    Some(step) => step,
+                    None => continue,
+                };
+
+                if let Some(next) = read(&state, *value, &outputs) {
+                    if visited.insert(next.clone()) {
+                        let mut path = inputs.clone();
+                        path.push(*value);
+                        queue.push_back((fork, next, path));
+                    }
+                }
+            }
+        }
+    }
+}
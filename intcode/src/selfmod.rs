@@ -0,0 +1,116 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use super::{Executer, Observer, OpCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeWriteKind {
+    // The address was part of an instruction that already ran.
+    ExecutedBefore,
+    // The address is decoded later on by the instruction at `counter`.
+    DecodedLater { counter: i128 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeWrite {
+    // The instruction that did the write.
+    pub counter: i128,
+    pub address: i128,
+    pub old: i128,
+    pub value: i128,
+    pub kind: CodeWriteKind,
+}
+
+impl std::fmt::Display for CodeWrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Instruction at {} wrote {} (was {}) to {}, ",
+            self.counter, self.value, self.old, self.address
+        )?;
+
+        match self.kind {
+            CodeWriteKind::ExecutedBefore => write!(f, "which was executed before"),
+            CodeWriteKind::DecodedLater { counter } => {
+                write!(
+                    f,
+                    "which is decoded later by the instruction at {}",
+                    counter
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SelfModificationData {
+    code: BTreeSet<i128>,
+    // Writes that did not hit code yet, by address.
+    written: BTreeMap<i128, (i128, i128, i128)>,
+    events: Vec<CodeWrite>,
+}
+
+// Detects writes into code. Clones share their data, so the events can be
+// read after the observer was attached to an executer.
+#[derive(Debug, Clone, Default)]
+pub struct SelfModification {
+    data: Arc<Mutex<SelfModificationData>>,
+}
+
+impl SelfModification {
+    pub fn new() -> Self {
+        SelfModification::default()
+    }
+
+    pub fn events(&self) -> Vec<CodeWrite> {
+        self.data.lock().unwrap().events.clone()
+    }
+
+    // True as long as the run never wrote into code.
+    pub fn is_static(&self) -> bool {
+        self.data.lock().unwrap().events.is_empty()
+    }
+}
+
+impl Observer for SelfModification {
+    fn instruction(&mut self, executer: &Executer, code: &OpCode) {
+        let mut data = self.data.lock().unwrap();
+        let counter = executer.counter();
+
+        for address in counter..counter.saturating_add(code.length().unwrap_or(1)) {
+            if let Some((writer, old, value)) = data.written.remove(&address) {
+                data.events.push(CodeWrite {
+                    counter: writer,
+                    address,
+                    old,
+                    value,
+                    kind: CodeWriteKind::DecodedLater { counter },
+                });
+            }
+            data.code.insert(address);
+        }
+    }
+
+    fn write(&mut self, executer: &Executer, address: i128, value: i128) {
+        let mut data = self.data.lock().unwrap();
+        let counter = executer.counter();
+        let old = executer.get(address).unwrap_or(0);
+
+        // Code that already ran is reported right away and not again when it
+        // is decoded a second time.
+        if data.code.contains(&address) {
+            data.events.push(CodeWrite {
+                counter,
+                address,
+                old,
+                value,
+                kind: CodeWriteKind::ExecutedBefore,
+            });
+            return;
+        }
+
+        // Keep the oldest value, so a later decode reports the full change.
+        let old = data.written.get(&address).map_or(old, |(_, old, _)| *old);
+        data.written.insert(address, (counter, old, value));
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+
+use super::{AsciiInterface, MachineError, MachineInterface, MachineResult, RunOutcome};
+
+// One value per line in both directions.
+struct NumberInterface {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl MachineInterface for NumberInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        writeln!(self.writer, "{}", value)?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        // The peer may be waiting for the output so far before it answers.
+        self.writer.flush()?;
+
+        loop {
+            let mut input = String::new();
+            if self.reader.read_line(&mut input)? == 0 {
+                return Ok(None);
+            }
+
+            let line = input.trim();
+            if !line.is_empty() {
+                let value = line.parse::<i128>().map_err(|_| MachineError {
+                    message: "Illegal input!".to_owned(),
+                    reason: format!("Cannot parse '{}' as an integer.", line),
+                })?;
+                return Ok(Some(value));
+            }
+        }
+    }
+
+    fn halt(&mut self, _outcome: &RunOutcome) -> MachineResult<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// Talks to a peer on the other end of a TCP stream, one value per line.
+// In ASCII mode the stream is read and written like a terminal by an
+// `AsciiInterface`.
+pub struct TcpInterface {
+    lines: Box<dyn MachineInterface>,
+    // Kept to end the stream once the program stopped.
+    stream: TcpStream,
+}
+
+impl TcpInterface {
+    pub fn new(stream: TcpStream, ascii: bool) -> MachineResult<Self> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let writer = BufWriter::new(stream.try_clone()?);
+        let lines: Box<dyn MachineInterface> = if ascii {
+            Box::new(AsciiInterface::with_io(reader, writer))
+        } else {
+            Box::new(NumberInterface { reader, writer })
+        };
+
+        Ok(TcpInterface { lines, stream })
+    }
+
+    pub fn connect(address: &str, ascii: bool) -> MachineResult<Self> {
+        TcpInterface::new(TcpStream::connect(address)?, ascii)
+    }
+
+    // Waits for a single peer, for example `nc localhost 4000`.
+    pub fn listen(address: &str, ascii: bool) -> MachineResult<Self> {
+        let (stream, _) = TcpListener::bind(address)?.accept()?;
+        TcpInterface::new(stream, ascii)
+    }
+}
+
+impl MachineInterface for TcpInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        self.lines.send(value)
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        self.lines.receive()
+    }
+
+    // The peer sees the end of the stream once the program stopped for
+    // good, a run waiting for input can still go on.
+    fn halt(&mut self, outcome: &RunOutcome) -> MachineResult<()> {
+        self.lines.halt(outcome)?;
+        match outcome {
+            RunOutcome::Exhausted | RunOutcome::Paused => {}
+            RunOutcome::Halted | RunOutcome::Cancelled | RunOutcome::Failed(_) => {
+                self.stream.shutdown(Shutdown::Write)?
+            }
+        }
+        Ok(())
+    }
+}
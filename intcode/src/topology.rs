@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+
+use super::{Executer, MachineError, MachineInterface, MachineResult, RunOutcome};
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub inputs: Vec<i128>,
+}
+
+// A network of executers that all run the same program. Every output of a
+// node is copied to all of its targets, and a node with several sources
+// reads their values in the order they arrive.
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Topology {
+    pub fn new() -> Self {
+        Topology::default()
+    }
+
+    pub fn parse_file(path: &str) -> MachineResult<Self> {
+        Topology::parse(&std::fs::read_to_string(path)?)
+    }
+
+    // Parses the topology format:
+    //
+    //   # five amplifiers in a feedback loop
+    //   node A 9 0
+    //   node B 8
+    //   ...
+    //   edge A -> B -> C -> D -> E -> A
+    pub fn parse(data: &str) -> MachineResult<Self> {
+        let mut topology = Topology::new();
+
+        for (number, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let error = |reason: String| MachineError {
+                message: "Illegal topology!".to_owned(),
+                reason: format!("Line {}: {}", number + 1, reason),
+            };
+
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                None => {}
+                Some("node") => {
+                    let name = parts
+                        .next()
+                        .ok_or_else(|| error("Node without a name.".to_owned()))?;
+                    let inputs = parts
+                        .map(|v| {
+                            v.trim_end_matches(',')
+                                .parse::<i128>()
+                                .map_err(|_| error(format!("Cannot parse input '{}'.", v)))
+                        })
+                        .collect::<MachineResult<Vec<_>>>()?;
+
+                    topology = topology.node(name, &inputs).map_err(|e| error(e.reason))?;
+                }
+                Some("edge") => {
+                    let names: Vec<&str> =
+                        line["edge".len()..].split("->").map(str::trim).collect();
+
+                    if names.len() < 2 || names.iter().any(|n| n.is_empty()) {
+                        return Err(error(format!(
+                            "Edge '{}' needs the form 'edge A -> B'.",
+                            line
+                        )));
+                    }
+
+                    for pair in names.windows(2) {
+                        topology = topology
+                            .edge(pair[0], pair[1])
+                            .map_err(|e| error(e.reason))?;
+                    }
+                }
+                Some(keyword) => return Err(error(format!("Unknown keyword '{}'.", keyword))),
+            }
+        }
+
+        Ok(topology)
+    }
+
+    pub fn node(mut self, name: &str, inputs: &[i128]) -> MachineResult<Self> {
+        if self.index(name).is_some() {
+            return Err(MachineError {
+                message: "Illegal topology!".to_owned(),
+                reason: format!("Node '{}' is defined twice.", name),
+            });
+        }
+
+        self.nodes.push(Node {
+            name: name.to_owned(),
+            inputs: inputs.to_owned(),
+        });
+        Ok(self)
+    }
+
+    pub fn edge(mut self, from: &str, to: &str) -> MachineResult<Self> {
+        let from = self.lookup(from)?;
+        let to = self.lookup(to)?;
+
+        self.edges.push((from, to));
+        Ok(self)
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+
+    fn lookup(&self, name: &str) -> MachineResult<usize> {
+        self.index(name).ok_or_else(|| MachineError {
+            message: "Illegal topology!".to_owned(),
+            reason: format!("Node '{}' is not defined.", name),
+        })
+    }
+
+    // Runs every node on its own thread until all of them have halted and
+    // returns the last output of each node, in node order. `first_number`
+    // is the executer number of the first node. A network in which every
+    // node waits for input that cannot arrive fails as a deadlock.
+    pub fn run(&self, program: &[i128], first_number: i128) -> MachineResult<Vec<Option<i128>>> {
+        self.run_with(program, first_number, |_, interface| Ok(interface))
+    }
+
+    // Like `run`, but `wrap` gets the index and interface of every node
+    // before it starts, for example to record its transcript.
+    pub fn run_with<F>(
+        &self,
+        program: &[i128],
+        first_number: i128,
+        mut wrap: F,
+    ) -> MachineResult<Vec<Option<i128>>>
+    where
+        F: FnMut(usize, Box<dyn MachineInterface>) -> MachineResult<Box<dyn MachineInterface>>,
+    {
+        let mut network = Network {
+            queues: self
+                .nodes
+                .iter()
+                .map(|n| n.inputs.iter().copied().collect())
+                .collect(),
+            open_sources: vec![0; self.nodes.len()],
+            running: vec![true; self.nodes.len()],
+            waiting: vec![false; self.nodes.len()],
+            deadlocked: false,
+        };
+        for (_, to) in &self.edges {
+            network.open_sources[*to] += 1;
+        }
+        let network = Arc::new((Mutex::new(network), Condvar::new()));
+
+        let last_outputs: Vec<Arc<Mutex<Option<i128>>>> = self
+            .nodes
+            .iter()
+            .map(|_| Arc::new(Mutex::new(None)))
+            .collect();
+
+        let mut interfaces = Vec::new();
+        for (index, last_output) in last_outputs.iter().enumerate() {
+            let interface = NodeInterface {
+                index,
+                targets: self
+                    .edges
+                    .iter()
+                    .filter(|(from, _)| *from == index)
+                    .map(|(_, to)| *to)
+                    .collect(),
+                network: network.clone(),
+                last_output: last_output.clone(),
+            };
+            interfaces.push(wrap(index, Box::new(interface))?);
+        }
+
+        let handles: Vec<_> = interfaces
+            .into_iter()
+            .enumerate()
+            .map(|(index, interface)| {
+                let program = program.to_owned();
+                let number = first_number + index as i128;
+
+                std::thread::spawn(move || {
+                    let mut executer = Executer::new(number, &program, interface, false);
+
+                    executer.execute()
+                })
+            })
+            .collect();
+
+        // Running out of input is a clean stop, every upstream node has halted.
+        let mut failed = Vec::new();
+        for (node, handle) in self.nodes.iter().zip(handles) {
+            match handle.join() {
+                Ok(RunOutcome::Halted) | Ok(RunOutcome::Exhausted) => {}
+                Ok(RunOutcome::Failed(error)) => {
+                    failed.push(format!("{}: {}", node.name, error.reason))
+                }
+                Ok(RunOutcome::Paused) | Ok(RunOutcome::Cancelled) | Err(_) => {
+                    failed.push(node.name.clone())
+                }
+            }
+        }
+
+        if network.0.lock().unwrap().deadlocked {
+            return Err(MachineError {
+                message: "Network deadlock!".to_owned(),
+                reason: "Every running node waits for input and no value is in flight.".to_owned(),
+            });
+        }
+
+        if !failed.is_empty() {
+            return Err(MachineError {
+                message: "Network node failed!".to_owned(),
+                reason: format!("Nodes {:?} stopped with an exception.", failed),
+            });
+        }
+
+        Ok(last_outputs
+            .iter()
+            .map(|last_output| *last_output.lock().unwrap())
+            .collect())
+    }
+}
+
+// The input queues of all nodes behind one lock, so a node that starts to
+// wait can tell whether the whole network is stuck.
+struct Network {
+    queues: Vec<VecDeque<i128>>,
+    // Upstream nodes that are still running, per node.
+    open_sources: Vec<usize>,
+    running: Vec<bool>,
+    waiting: Vec<bool>,
+    deadlocked: bool,
+}
+
+impl Network {
+    // No running node can go on: each one waits with an empty queue for
+    // sources that are still running.
+    fn is_stuck(&self) -> bool {
+        (0..self.queues.len()).all(|node| {
+            !self.running[node]
+                || (self.waiting[node]
+                    && self.queues[node].is_empty()
+                    && self.open_sources[node] > 0)
+        })
+    }
+}
+
+struct NodeInterface {
+    index: usize,
+    targets: Vec<usize>,
+    network: Arc<(Mutex<Network>, Condvar)>,
+    last_output: Arc<Mutex<Option<i128>>>,
+}
+
+impl MachineInterface for NodeInterface {
+    fn send(&mut self, value: i128) -> MachineResult<()> {
+        *self.last_output.lock().unwrap() = Some(value);
+
+        // A target may already have halted and will never read this value.
+        // That is a normal way for a feedback loop to end.
+        let (network, changed) = &*self.network;
+        let mut network = network.lock().unwrap();
+        for target in &self.targets {
+            network.queues[*target].push_back(value);
+        }
+        changed.notify_all();
+        Ok(())
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        let (network, changed) = &*self.network;
+        let mut network = network.lock().unwrap();
+
+        loop {
+            if let Some(value) = network.queues[self.index].pop_front() {
+                return Ok(Some(value));
+            }
+            // All sources have halted.
+            if network.open_sources[self.index] == 0 {
+                return Ok(None);
+            }
+
+            network.waiting[self.index] = true;
+            if network.is_stuck() {
+                network.deadlocked = true;
+                changed.notify_all();
+            }
+            if network.deadlocked {
+                network.waiting[self.index] = false;
+                return Err(MachineError {
+                    message: "Network deadlock!".to_owned(),
+                    reason: format!("Node {} waits for input that never arrives.", self.index),
+                });
+            }
+
+            network = changed.wait(network).unwrap();
+            network.waiting[self.index] = false;
+        }
+    }
+}
+
+// Dropped once the node stopped, also if its thread panicked.
+impl Drop for NodeInterface {
+    fn drop(&mut self) {
+        let (network, changed) = &*self.network;
+        if let Ok(mut network) = network.lock() {
+            network.running[self.index] = false;
+            for target in &self.targets {
+                network.open_sources[*target] -= 1;
+            }
+            changed.notify_all();
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::fmt::Write;
+
+use super::{decode, trace_code, Instruction, Param};
+
+fn read(param: &Param) -> String {
+    match *param {
+        Param::Position(address) => format!("m.get({})", address),
+        Param::Immediate(value) => format!("{}", value),
+        Param::Relative(offset) => format!("m.get(relative + {})", offset),
+    }
+}
+
+fn address(param: &Param) -> String {
+    match *param {
+        Param::Position(address) => format!("{}", address),
+        Param::Immediate(value) => format!("{}", value),
+        Param::Relative(offset) => format!("relative + {}", offset),
+    }
+}
+
+fn store(target: &Param, value: &str, next: i128) -> String {
+    format!(
+        "if m.set({}, {}) {{\n                    return fallback(m, {}, relative, interface);\n                }}\n                counter = {};",
+        address(target),
+        value,
+        next,
+        next
+    )
+}
+
+// The interpreter reports an overflow as the error it is, so the run
+// continues there in front of the instruction.
+fn checked(value: &str) -> String {
+    format!(
+        "match {} {{\n                    Some(value) => value,\n                    None => return fallback(m, counter, relative, interface),\n                }}",
+        value
+    )
+}
+
+fn arm(instruction: &Instruction) -> String {
+    let mut guards = String::new();
+    for param in &instruction.params {
+        if let Param::Relative(offset) = param {
+            let _ = write!(
+                guards,
+                "if relative.checked_add({}).is_none() {{\n                    return fallback(m, counter, relative, interface);\n                }}\n                ",
+                offset
+            );
+        }
+    }
+    guards + &operation(instruction)
+}
+
+fn operation(instruction: &Instruction) -> String {
+    let p = &instruction.params;
+    let next = instruction.address + instruction.length();
+
+    match instruction.op {
+        1 | 2 => format!(
+            "let value = {};\n                {}",
+            checked(&format!(
+                "i128::checked_{}({}, {})",
+                if instruction.op == 1 { "add" } else { "mul" },
+                read(&p[0]),
+                read(&p[1])
+            )),
+            store(&p[2], "value", next)
+        ),
+        7 => store(
+            &p[2],
+            &format!("({} < {}) as i128", read(&p[0]), read(&p[1])),
+            next,
+        ),
+        8 => store(
+            &p[2],
+            &format!("({} == {}) as i128", read(&p[0]), read(&p[1])),
+            next,
+        ),
+        3 => format!(
+            "let value = match interface.receive() {{\n                    Ok(Some(value)) => value,\n                    Ok(None) => return finish(&mut interface, RunOutcome::Exhausted),\n                    Err(error) => return finish(&mut interface, RunOutcome::Failed(error)),\n                }};\n                {}",
+            store(&p[0], "value", next)
+        ),
+        4 => format!(
+            "if let Err(error) = interface.send({}) {{\n                    return finish(&mut interface, RunOutcome::Failed(error));\n                }}\n                counter = {};",
+            read(&p[0]),
+            next
+        ),
+        5 => format!(
+            "counter = if {} != 0 {{ {} }} else {{ {} }};",
+            read(&p[0]),
+            read(&p[1]),
+            next
+        ),
+        6 => format!(
+            "counter = if {} == 0 {{ {} }} else {{ {} }};",
+            read(&p[0]),
+            read(&p[1]),
+            next
+        ),
+        9 => format!(
+            "relative = {};\n                counter = {};",
+            checked(&format!("relative.checked_add({})", read(&p[0]))),
+            next
+        ),
+        _ => "return finish(&mut interface, RunOutcome::Halted);".to_owned(),
+    }
+}
+
+// Merges the words of all instructions into inclusive address ranges.
+fn code_ranges(instructions: &[&Instruction]) -> Vec<(i128, i128)> {
+    let mut ranges: Vec<(i128, i128)> = Vec::new();
+
+    for instruction in instructions {
+        let start = instruction.address;
+        let end = start + instruction.length() - 1;
+
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+}
+
+// Generates a Rust module with `pub fn run(interface) -> RunOutcome` that
+// executes `program` natively: a `match` on the counter with one arm per
+// instruction reachable from address 0 and `entries`. Every other address
+// that decodes gets a guarded arm as well, since jumps through the stack
+// land there; the guard checks that the instruction words were never
+// written. When a write hits traced code, a guard fails or the counter
+// reaches an address without an arm, the run continues in the interpreter.
+pub fn transpile(program: &[i128], entries: &[i128]) -> String {
+    let mut starts = vec![0];
+    starts.extend_from_slice(entries);
+    let code = trace_code(program, &starts);
+    let instructions: Vec<&Instruction> = code.values().collect();
+
+    let ranges = code_ranges(&instructions);
+    let is_code = if ranges.is_empty() {
+        "false".to_owned()
+    } else {
+        let patterns: Vec<String> = ranges
+            .iter()
+            .map(|(start, end)| format!("{}..={}", start, end))
+            .collect();
+        format!("matches!(address, {})", patterns.join(" | "))
+    };
+
+    let words: Vec<String> = program.iter().map(|v| v.to_string()).collect();
+
+    let mut source = String::new();
+    let _ = write!(
+        source,
+        r#"// Generated by intcode::transpile from a program of {length} words.
+use std::collections::BTreeMap;
+
+use intcode::{{Executer, MachineInterface, RunOutcome}};
+
+const PROGRAM: [i128; {length}] = [{words}];
+
+struct Memory {{
+    words: Vec<i128>,
+    extra: BTreeMap<i128, i128>,
+    written: Vec<bool>,
+}}
+
+impl Memory {{
+    fn get(&self, address: i128) -> i128 {{
+        if 0 <= address && address < self.words.len() as i128 {{
+            self.words[address as usize]
+        }} else {{
+            *self.extra.get(&address).unwrap_or(&0)
+        }}
+    }}
+
+    // Returns true if the write hit transpiled code.
+    fn set(&mut self, address: i128, value: i128) -> bool {{
+        if 0 <= address && address < self.words.len() as i128 {{
+            self.words[address as usize] = value;
+            self.written[address as usize] = true;
+        }} else {{
+            self.extra.insert(address, value);
+        }}
+        is_code(address)
+    }}
+
+    fn changed(&self, address: i128, length: usize) -> bool {{
+        let address = address as usize;
+        self.written[address..address + length].iter().any(|w| *w)
+    }}
+}}
+
+fn is_code(address: i128) -> bool {{
+    {is_code}
+}}
+
+fn finish(interface: &mut Box<dyn MachineInterface>, outcome: RunOutcome) -> RunOutcome {{
+    match interface.halt(&outcome) {{
+        Ok(()) => outcome,
+        Err(error) => match outcome {{
+            RunOutcome::Failed(_) => outcome,
+            _ => RunOutcome::Failed(error),
+        }},
+    }}
+}}
+
+fn fallback(
+    m: Memory,
+    counter: i128,
+    relative: i128,
+    interface: Box<dyn MachineInterface>,
+) -> RunOutcome {{
+    Executer::from_state(0, m.words, m.extra, counter, relative, interface).execute()
+}}
+
+#[allow(clippy::all, unreachable_code, unused_mut, unused_parens)]
+pub fn run(mut interface: Box<dyn MachineInterface>) -> RunOutcome {{
+    let mut m = Memory {{
+        words: PROGRAM.to_vec(),
+        extra: BTreeMap::new(),
+        written: vec![false; PROGRAM.len()],
+    }};
+    let mut counter: i128 = 0;
+    let mut relative: i128 = 0;
+
+    loop {{
+        match counter {{
+"#,
+        length = program.len(),
+        words = words.join(", "),
+        is_code = is_code,
+    );
+
+    for instruction in &instructions {
+        let _ = write!(
+            source,
+            "            // {}\n            {} => {{\n                {}\n            }}\n",
+            instruction,
+            instruction.address,
+            arm(instruction)
+        );
+    }
+
+    for address in 0..program.len() as i128 {
+        if code.contains_key(&address) {
+            continue;
+        }
+        if let Some(instruction) = decode(program, address) {
+            let _ = write!(
+                source,
+                "            // {}\n            {} => {{\n                if m.changed({}, {}) {{\n                    return fallback(m, counter, relative, interface);\n                }}\n                {}\n            }}\n",
+                instruction,
+                address,
+                address,
+                instruction.length(),
+                arm(&instruction)
+            );
+        }
+    }
+
+    source.push_str(
+        "            _ => return fallback(m, counter, relative, interface),\n        }\n    }\n}\n",
+    );
+
+    source
+}
@@ -0,0 +1,34 @@
+#[derive(Debug)]
+pub struct OpCode {
+    code: i128,
+}
+
+impl OpCode {
+    pub fn new(code: i128) -> Self {
+        OpCode { code }
+    }
+
+    pub fn code(&self) -> i128 {
+        self.code
+    }
+
+    pub fn op(&self) -> i128 {
+        self.code % 100
+    }
+
+    pub fn mode(&self, pos: i128) -> i128 {
+        let position = 10_i128.pow(pos as u32 + 1);
+        self.code / position % 10
+    }
+
+    // Number of words including the parameters, `None` for unknown opcodes.
+    pub fn length(&self) -> Option<i128> {
+        match self.op() {
+            1 | 2 | 7 | 8 => Some(4),
+            5 | 6 => Some(3),
+            3 | 4 | 9 => Some(2),
+            99 => Some(1),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex};
+
+use intcode::{AsciiInterface, Executer, RunOutcome};
+
+// Collects what the interface writes, so it can be read after the run.
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run(program: &[i128], input: &str) -> (RunOutcome, String) {
+    let output = Output::default();
+    let interface = AsciiInterface::with_io(Cursor::new(input.to_owned()), output.clone());
+    let outcome = Executer::new(0, program, Box::new(interface), false).execute();
+
+    let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    (outcome, text)
+}
+
+// Echoes its input up to and including the first newline.
+const ECHO: [i128; 12] = [3, 100, 4, 100, 1008, 100, 10, 101, 1006, 101, 0, 99];
+
+#[test]
+fn lines_become_code_points_and_a_newline() {
+    let (outcome, output) = run(&ECHO, "hi\r\nrest\n");
+    assert!(matches!(outcome, RunOutcome::Halted));
+    assert_eq!(output, "hi\n");
+}
+
+#[test]
+fn large_values_are_printed_as_numbers() {
+    let (outcome, output) = run(&[104, 1000, 104, 65, 104, 128, 99], "");
+    assert!(matches!(outcome, RunOutcome::Halted));
+    assert_eq!(output, "1000\nA128\n");
+}
+
+#[test]
+fn end_of_input_exhausts_the_run() {
+    let (outcome, output) = run(&ECHO, "");
+    assert!(matches!(outcome, RunOutcome::Exhausted));
+    assert_eq!(output, "");
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+
+use intcode::{compile_program, CallStack, Debugger, Executer, Frame, QueueInterface, RunOutcome};
+
+const FIB: &str = "
+fn fib(n) {
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+fn main() {
+    output(fib(input()));
+}
+";
+
+fn names(labels: &BTreeMap<String, i128>) -> BTreeMap<i128, String> {
+    labels
+        .iter()
+        .filter(|(name, _)| !name.starts_with('.'))
+        .map(|(name, address)| (*address, name.clone()))
+        .collect()
+}
+
+#[test]
+fn compiled_recursion_folds_into_nested_stacks() {
+    let assembly = compile_program(FIB).unwrap();
+    let interface = QueueInterface::new();
+    interface.push_input(&[6]);
+
+    let calls = CallStack::new();
+    let mut executer = Executer::new(0, &assembly.program, Box::new(interface.clone()), false);
+    executer.add_observer(Box::new(calls.clone()));
+    assert!(matches!(executer.execute(), RunOutcome::Halted));
+    assert_eq!(interface.take_output(), vec![8]);
+
+    // All calls returned.
+    assert!(calls.frames().is_empty());
+
+    let folded = calls.folded(&names(&assembly.labels));
+    let stacks: Vec<&str> = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert!(stacks.contains(&"@0;main"));
+    assert!(stacks.contains(&"@0;main;fib;fib;fib;fib;fib;fib"));
+    assert!(!stacks.contains(&"@0;main;fib;fib;fib;fib;fib;fib;fib"));
+}
+
+#[test]
+fn relative_base_pushes_and_pops_frames() {
+    // Calls 10 by raising the base and returns by lowering it again, the
+    // return jump goes to an immediate address.
+    let program = [109, 2, 1105, 1, 10, 104, 42, 99, 0, 0, 109, -2, 1105, 1, 5];
+    let executer = Executer::new(0, &program, Box::new(QueueInterface::new()), false);
+    let mut debugger = Debugger::new(executer);
+    debugger.add_breakpoint(10);
+    debugger.add_breakpoint(12);
+
+    assert!(matches!(debugger.resume(), RunOutcome::Paused));
+    assert_eq!(
+        debugger.call_stack(),
+        vec![Frame {
+            function: 10,
+            call_site: 2,
+            return_address: 5,
+            relative: 2,
+        }]
+    );
+
+    assert!(matches!(debugger.resume(), RunOutcome::Paused));
+    assert!(debugger.call_stack().is_empty());
+
+    assert!(matches!(debugger.resume(), RunOutcome::Halted));
+    let names = vec![(10, "f".to_owned())].into_iter().collect();
+    assert_eq!(debugger.calls().folded(&names), "@0 5\n@0;f 1");
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use intcode::{channel_with_mode, ChannelInterface, ChannelMode, Executer, RunOutcome};
+
+#[test]
+fn exhausted_run_resumes_with_open_output() {
+    let interface = ChannelInterface::new();
+    interface.in_sender.close();
+    let output = interface.out_receiver.clone();
+
+    // Reads into 20, then outputs 5.
+    let mut executer = Executer::new(0, &[3, 20, 104, 5, 99], Box::new(interface), false);
+    assert!(matches!(executer.execute(), RunOutcome::Exhausted));
+    assert_eq!(output.end_status().map(|_| ()), None);
+
+    executer.set(20, 9).unwrap();
+    executer.set_counter(2);
+    assert!(matches!(executer.execute(), RunOutcome::Halted));
+
+    assert_eq!(output.recv(), Some(5));
+    assert_eq!(output.recv(), None);
+    assert!(matches!(output.end_status(), Some(RunOutcome::Halted)));
+}
+
+#[test]
+fn unbounded_channel_tracks_its_depth() {
+    let (sender, receiver) = channel_with_mode(ChannelMode::Unbounded);
+    for value in 1..=3 {
+        sender.send(value).unwrap();
+    }
+    assert_eq!(receiver.recv(), Some(1));
+
+    let stats = receiver.stats();
+    assert_eq!((stats.depth, stats.max_depth), (2, 3));
+    assert_eq!((stats.sent, stats.received, stats.dropped), (3, 1, 0));
+}
+
+#[test]
+fn drop_oldest_keeps_the_newest_values() {
+    let (sender, receiver) = channel_with_mode(ChannelMode::DropOldest(2));
+    for value in 1..=5 {
+        sender.send(value).unwrap();
+    }
+    sender.close();
+
+    assert_eq!(receiver.recv(), Some(4));
+    assert_eq!(receiver.recv(), Some(5));
+    assert_eq!(receiver.recv(), None);
+
+    let stats = sender.stats();
+    assert_eq!((stats.sent, stats.dropped, stats.max_depth), (5, 3, 2));
+}
+
+#[test]
+fn bounded_sender_waits_for_the_receiver() {
+    let (sender, receiver) = channel_with_mode(ChannelMode::Bounded(1));
+    let writer = std::thread::spawn(move || {
+        for value in 1..=3 {
+            sender.send(value).unwrap();
+        }
+        sender.stats()
+    });
+
+    std::thread::sleep(Duration::from_millis(50));
+    let values: Vec<i128> = std::iter::from_fn(|| receiver.recv()).collect();
+    assert_eq!(values, vec![1, 2, 3]);
+
+    let stats = writer.join().unwrap();
+    assert_eq!((stats.max_depth, stats.dropped), (1, 0));
+    assert!(stats.send_blocked >= Duration::from_millis(40));
+}
+
+#[test]
+fn receiver_waits_for_the_sender() {
+    let (sender, receiver) = channel_with_mode(ChannelMode::Unbounded);
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        sender.send(7).unwrap();
+    });
+
+    assert_eq!(receiver.recv(), Some(7));
+    writer.join().unwrap();
+    assert!(receiver.stats().receive_blocked >= Duration::from_millis(40));
+}
@@ -0,0 +1,112 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Runs the command line tool with the program in a file of its own.
+fn intcode(name: &str, program: &str, args: &[&str]) -> Output {
+    let path = std::env::temp_dir().join(format!("intcode-cli-{}.txt", name));
+    std::fs::write(&path, program).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .arg(args[0])
+        .arg(&path)
+        .args(&args[1..])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn no_steps_run_no_instructions() {
+    let output = intcode("steps", "104,1,99", &["run", "--max-steps", "0"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+}
+
+#[test]
+fn step_limit_is_reported() {
+    let output = intcode("limit", "1105,1,0", &["run", "--max-steps", "5"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Stopped after 5 steps at 0.\n"
+    );
+}
+
+#[test]
+fn ascii_mode_reads_lines_from_the_terminal() {
+    // Echoes the first line, then prints 1000.
+    let path = std::env::temp_dir().join("intcode-cli-ascii.txt");
+    std::fs::write(&path, "3,100,4,100,1008,100,10,101,1006,101,0,104,1000,99").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .arg("run")
+        .arg(&path)
+        .arg("--ascii")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"hi\n").unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n1000\n");
+}
+
+#[test]
+fn read_prints_memory_after_the_run() {
+    let output = intcode("read", "1101,2,3,0,99", &["run", "--read", "0,4"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0=5\n4=99\n");
+}
+
+#[test]
+fn trace_goes_to_stderr() {
+    let output = intcode("trace", "104,7,99", &["run", "--trace"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "7\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("output 7"));
+}
+
+#[test]
+fn closed_stdout_ends_quietly() {
+    let path = std::env::temp_dir().join("intcode-cli-pipe.txt");
+    std::fs::write(&path, "104,1,".repeat(10_000) + "99").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .arg("disassemble")
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Like `| head -0`, nobody reads the output.
+    drop(child.stdout.take());
+
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn closed_stdout_ends_an_ascii_run_quietly() {
+    let path = std::env::temp_dir().join("intcode-cli-ascii-pipe.txt");
+    std::fs::write(&path, "104,65,".repeat(100_000) + "99").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .arg("run")
+        .arg(&path)
+        .arg("--ascii")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
@@ -0,0 +1,114 @@
+use intcode::{compile, compile_program, Executer, QueueInterface, RunOutcome};
+
+fn run(source: &str, inputs: &[i128]) -> (RunOutcome, Vec<i128>) {
+    let assembly = compile_program(source).unwrap_or_else(|error| panic!("{}", error.reason));
+    let interface = QueueInterface::new();
+    interface.push_input(inputs);
+
+    let mut executer = Executer::new(0, &assembly.program, Box::new(interface.clone()), false);
+    let outcome = executer.execute();
+    (outcome, interface.take_output())
+}
+
+fn outputs(source: &str, inputs: &[i128]) -> Vec<i128> {
+    match run(source, inputs) {
+        (RunOutcome::Halted, outputs) => outputs,
+        (outcome, _) => panic!("unexpected outcome {:?}", outcome),
+    }
+}
+
+fn overflow(source: &str) {
+    match run(source, &[]) {
+        (RunOutcome::Failed(error), _) => assert_eq!(error.message, "Arithmetic overflow!"),
+        (outcome, _) => panic!("unexpected outcome {:?}", outcome),
+    }
+}
+
+#[test]
+fn expressions_follow_precedence() {
+    let source = "
+fn main() {
+    let a = input();
+    let b = input();
+    output(a + b * 2);
+    output((a + b) * 2);
+    output(a - b - 1);
+    output(-a + !b);
+    output(a < b);
+    output(a >= b);
+    output(a == 3 && b != 3);
+    output(a == 4 || b == 4);
+}
+";
+    assert_eq!(outputs(source, &[3, 4]), vec![11, 14, -2, -3, 1, 0, 1, 1]);
+}
+
+#[test]
+fn constants_are_folded() {
+    let source = "fn main() { output(2 + 3 * 4 - -1); output(!0); output(1 < 2); }";
+    assert_eq!(outputs(source, &[]), vec![15, 1, 1]);
+}
+
+#[test]
+fn functions_take_arguments_and_recurse() {
+    let source = "
+fn sub(a, b) {
+    return a - b;
+}
+
+fn fact(n) {
+    if n < 2 {
+        return 1;
+    }
+    return n * fact(n - 1);
+}
+
+fn main() {
+    output(sub(10, input()));
+    output(fact(10));
+}
+";
+    assert_eq!(outputs(source, &[3]), vec![7, 3_628_800]);
+}
+
+#[test]
+fn control_flow_branches_and_loops() {
+    let source = "
+fn main() {
+    let n = input();
+    let sum = 0;
+    while n > 0 {
+        if n == 2 {
+            output(n);
+        } else {
+            sum = sum + n;
+        }
+        n = n - 1;
+    }
+    output(sum);
+}
+";
+    assert_eq!(outputs(source, &[5]), vec![2, 13]);
+}
+
+#[test]
+fn overflowing_constants_fail_at_run_time() {
+    let max = i128::MAX;
+    overflow(&format!("fn main() {{ output({} + 1); }}", max));
+    overflow(&format!("fn main() {{ output(0 - {} - 2); }}", max));
+    overflow(&format!("fn main() {{ output({} * 2); }}", max));
+    overflow(&format!("fn main() {{ output(-(0 - {} - 1)); }}", max));
+}
+
+#[test]
+fn mistakes_are_compile_errors() {
+    let error = compile("fn main() { output(missing(1)); }").unwrap_err();
+    assert_eq!(error.message, "Compile error!");
+    assert_eq!(error.reason, "Line 1: Unknown function 'missing'.");
+
+    let error = compile("fn f(a) { return a; }\nfn main() { f(1, 2); }").unwrap_err();
+    assert_eq!(error.reason, "Line 2: Wrong number of arguments for 'f'.");
+
+    let error = compile("fn main() { output(x); }").unwrap_err();
+    assert_eq!(error.reason, "Line 1: Unknown variable 'x'.");
+}
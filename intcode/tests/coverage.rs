@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use intcode::{Coverage, Executer, QueueInterface, RunOutcome};
+
+// Counts [12] down from 3, the loop starts at 4.
+const LOOP: [i128; 13] = [1101, 3, 0, 12, 1001, 12, -1, 12, 1005, 12, 4, 99, 0];
+
+fn covered(program: &[i128]) -> Coverage {
+    let coverage = Coverage::new();
+    let mut executer = Executer::new(0, program, Box::new(QueueInterface::new()), false);
+    executer.add_observer(Box::new(coverage.clone()));
+
+    assert!(matches!(executer.execute(), RunOutcome::Halted));
+    coverage
+}
+
+#[test]
+fn counts_hits_per_instruction() {
+    let coverage = covered(&LOOP);
+
+    let expected: BTreeMap<i128, u64> = vec![(0, 1), (4, 3), (8, 3), (11, 1)].into_iter().collect();
+    assert_eq!(coverage.hits(), expected);
+}
+
+#[test]
+fn reports_code_data_and_branches() {
+    let coverage = covered(&LOOP);
+    let report = coverage.report(&LOOP);
+
+    assert_eq!(report.code, 12);
+    assert_eq!(report.data, 1);
+    assert_eq!(report.untouched, 0);
+    assert_eq!(report.branches.get(&8), Some(&(true, true)));
+
+    let listing = coverage.annotate(&LOOP);
+    assert!(listing
+        .lines()
+        .any(|line| line.contains("X          3  add")));
+}
+
+#[test]
+fn instruction_at_the_last_address_fails_the_run() {
+    let coverage = Coverage::new();
+    let mut executer = Executer::new(0, &[99], Box::new(QueueInterface::new()), false);
+    executer.add_observer(Box::new(coverage.clone()));
+    executer.set(i128::MAX, 1101).unwrap();
+    executer.set_counter(i128::MAX);
+
+    match executer.execute() {
+        RunOutcome::Failed(error) => assert_eq!(error.message, "Arithmetic overflow!"),
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    assert_eq!(coverage.hits().get(&i128::MAX), Some(&1));
+}
@@ -0,0 +1,146 @@
+use std::io::Cursor;
+
+use intcode::{serve_dap, Json};
+
+fn frame(text: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", text.len(), text)
+}
+
+// Serves the requests against the program and returns every message sent
+// back, in order.
+fn session(name: &str, program: &str, requests: &[&str]) -> Vec<Json> {
+    let path = std::env::temp_dir().join(format!("intcode-dap-{}.txt", name));
+    std::fs::write(&path, program).unwrap();
+
+    let launch = format!(
+        r#"{{"seq":2,"type":"request","command":"launch","arguments":{{"program":{:?},"input":[42],"stopOnEntry":true}}}}"#,
+        path.to_str().unwrap()
+    );
+    let mut input = frame(r#"{"seq":1,"type":"request","command":"initialize"}"#);
+    input.push_str(&frame(&launch));
+    for request in requests {
+        input.push_str(&frame(request));
+    }
+
+    let mut output = Vec::new();
+    serve_dap(Cursor::new(input.into_bytes()), &mut output).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let text = String::from_utf8(output).unwrap();
+    text.split("Content-Length: ")
+        .filter(|message| !message.is_empty())
+        .map(|message| Json::parse(message.split_once("\r\n\r\n").unwrap().1).unwrap())
+        .collect()
+}
+
+fn stopped(messages: &[Json]) -> Vec<&str> {
+    messages
+        .iter()
+        .filter(|message| message.get("event").as_str() == Some("stopped"))
+        .map(|message| message.get("body").get("reason").as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn malformed_message_is_answered_and_reading_goes_on() {
+    let messages = session(
+        "malformed",
+        "3,0,4,0,99",
+        &[
+            "{oops",
+            r#"{"seq":3,"type":"request","command":"configurationDone"}"#,
+            r#"{"seq":4,"type":"request","command":"disconnect"}"#,
+        ],
+    );
+
+    let error = messages
+        .iter()
+        .find(|message| message.get("success").as_bool() == Some(false))
+        .unwrap();
+    assert_eq!(error.get("command").as_str(), Some(""));
+    assert_eq!(stopped(&messages), vec!["entry"]);
+    assert!(messages
+        .iter()
+        .any(|message| message.get("command").as_str() == Some("disconnect")));
+}
+
+#[test]
+fn stop_on_entry_with_a_breakpoint_at_zero() {
+    let messages = session(
+        "entry",
+        "3,0,4,0,99",
+        &[
+            r#"{"seq":3,"type":"request","command":"setInstructionBreakpoints","arguments":{"breakpoints":[{"instructionReference":"0"}]}}"#,
+            r#"{"seq":4,"type":"request","command":"configurationDone"}"#,
+            r#"{"seq":5,"type":"request","command":"continue"}"#,
+            r#"{"seq":6,"type":"request","command":"disconnect"}"#,
+        ],
+    );
+
+    assert_eq!(stopped(&messages), vec!["breakpoint"]);
+    let output = messages
+        .iter()
+        .find(|message| message.get("event").as_str() == Some("output"))
+        .unwrap();
+    assert_eq!(output.get("body").get("output").as_str(), Some("42\n"));
+    assert!(messages
+        .iter()
+        .any(|message| message.get("event").as_str() == Some("exited")));
+}
+
+fn response<'a>(messages: &'a [Json], command: &str) -> &'a Json {
+    messages
+        .iter()
+        .find(|message| message.get("command").as_str() == Some(command))
+        .unwrap()
+        .get("body")
+}
+
+#[test]
+fn addresses_at_the_end_of_the_address_space() {
+    let max = i128::MAX;
+    let breakpoints = format!(
+        r#"{{"seq":3,"type":"request","command":"setInstructionBreakpoints","arguments":{{"breakpoints":[{{"instructionReference":"{}","offset":1}}]}}}}"#,
+        max
+    );
+    let variables = format!(
+        r#"{{"seq":4,"type":"request","command":"variables","arguments":{{"variablesReference":3,"start":{},"count":{}}}}}"#,
+        max, max
+    );
+    let disassemble = format!(
+        r#"{{"seq":5,"type":"request","command":"disassemble","arguments":{{"memoryReference":"{}","instructionCount":{}}}}}"#,
+        max - 1,
+        max
+    );
+    let messages = session(
+        "overflow",
+        "3,0,4,0,99",
+        &[
+            &breakpoints,
+            &variables,
+            &disassemble,
+            r#"{"seq":6,"type":"request","command":"disconnect"}"#,
+        ],
+    );
+
+    let breakpoint = &response(&messages, "setInstructionBreakpoints")
+        .get("breakpoints")
+        .as_array()
+        .unwrap()[0];
+    assert_eq!(breakpoint.get("verified").as_bool(), Some(false));
+    let variables = response(&messages, "variables").get("variables");
+    assert_eq!(variables.as_array().unwrap().len(), 0);
+    let instructions = response(&messages, "disassemble").get("instructions");
+    assert_eq!(instructions.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn oversized_message_ends_the_session() {
+    let mut input = frame(r#"{"seq":1,"type":"request","command":"initialize"}"#);
+    input.push_str("Content-Length: 100000000000\r\n\r\n");
+
+    let mut output = Vec::new();
+    serve_dap(Cursor::new(input.into_bytes()), &mut output).unwrap();
+    let text = String::from_utf8(output).unwrap();
+    assert_eq!(text.matches("Content-Length: ").count(), 2);
+}
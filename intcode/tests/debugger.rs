@@ -0,0 +1,76 @@
+use intcode::{
+    evaluate_expression, Condition, Debugger, Executer, QueueInterface, RunOutcome, StopReason,
+};
+
+fn debugger(program: &[i128], input: &[i128]) -> (Debugger, QueueInterface) {
+    let interface = QueueInterface::new();
+    interface.push_input(input);
+    let executer = Executer::new(0, program, Box::new(interface.clone()), false);
+    (Debugger::new(executer), interface)
+}
+
+#[test]
+fn breakpoint_on_the_entry_instruction() {
+    let (mut debugger, interface) = debugger(&[3, 0, 4, 0, 99], &[42]);
+    debugger.add_breakpoint(0);
+
+    assert!(matches!(debugger.resume(), RunOutcome::Paused));
+    assert_eq!(debugger.reason(), Some(StopReason::Breakpoint(0)));
+    assert_eq!(debugger.executer().counter(), 0);
+
+    assert!(matches!(debugger.resume(), RunOutcome::Halted));
+    assert_eq!(interface.take_output(), vec![42]);
+}
+
+#[test]
+fn breakpoint_in_a_loop_stops_every_iteration() {
+    // Counts [12] down from 3, the loop starts at 4.
+    let program = [1101, 3, 0, 12, 1001, 12, -1, 12, 1005, 12, 4, 99, 0];
+    let (mut debugger, _) = debugger(&program, &[]);
+    debugger.add_breakpoint(4);
+
+    let mut hits = 0;
+    let outcome = loop {
+        match debugger.resume() {
+            RunOutcome::Paused => hits += 1,
+            outcome => break outcome,
+        }
+    };
+    assert!(matches!(outcome, RunOutcome::Halted));
+    assert_eq!(hits, 3);
+}
+
+#[test]
+fn step_executes_the_instruction_under_a_breakpoint() {
+    let (mut debugger, _) = debugger(&[1101, 1, 2, 0, 99], &[]);
+    debugger.add_breakpoint(0);
+
+    assert!(matches!(debugger.step(), RunOutcome::Paused));
+    assert_eq!(debugger.executer().counter(), 4);
+    assert_eq!(debugger.executer().get(0).unwrap(), 3);
+}
+
+#[test]
+fn overflowing_expressions_are_errors() {
+    let (debugger, _) = debugger(&[99], &[]);
+    let executer = debugger.executer();
+
+    let error = evaluate_expression("170141183460469231731687303715884105727 + 1", executer);
+    assert_eq!(error.unwrap_err().message, "Arithmetic overflow!");
+    let error = evaluate_expression(
+        "counter - 170141183460469231731687303715884105727 - 2",
+        executer,
+    );
+    assert_eq!(error.unwrap_err().message, "Arithmetic overflow!");
+    assert_eq!(evaluate_expression("[0] + 1", executer).unwrap(), 100);
+}
+
+#[test]
+fn overflowing_condition_does_not_stop_the_run() {
+    let (mut debugger, interface) = debugger(&[104, 1, 99], &[]);
+    let condition = Condition::parse("counter + 170141183460469231731687303715884105727 < 0");
+    debugger.add_condition(condition.unwrap());
+
+    assert!(matches!(debugger.resume(), RunOutcome::Halted));
+    assert_eq!(interface.take_output(), vec![1]);
+}
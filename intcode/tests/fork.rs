@@ -0,0 +1,33 @@
+use intcode::{Executer, QueueInterface, RunOutcome};
+
+// Reads into [20], copies it to [100] and outputs it. Both cells lie
+// beyond the program.
+const COPY: [i128; 9] = [3, 20, 1001, 20, 0, 100, 4, 100, 99];
+
+#[test]
+fn fork_keeps_memory_and_interface_apart() {
+    let parent_io = QueueInterface::new();
+    let mut parent = Executer::new(0, &COPY, Box::new(parent_io.clone()), false);
+    parent.set(100, 7).unwrap();
+    assert!(matches!(parent.execute(), RunOutcome::Exhausted));
+
+    let child_io = QueueInterface::new();
+    let mut child = parent.fork(Box::new(child_io.clone()));
+    // The fork starts from the state of its parent.
+    assert_eq!(child.counter(), parent.counter());
+    assert_eq!(child.get(100).unwrap(), 7);
+
+    child_io.push_input(&[2]);
+    assert!(matches!(child.execute(), RunOutcome::Halted));
+    assert_eq!(child_io.take_output(), vec![2]);
+    assert_eq!(parent_io.take_output(), vec![]);
+    assert_eq!(parent.get(20).unwrap(), 0);
+    assert_eq!(parent.get(100).unwrap(), 7);
+
+    parent_io.push_input(&[1]);
+    assert!(matches!(parent.execute(), RunOutcome::Halted));
+    assert_eq!(parent_io.take_output(), vec![1]);
+    assert_eq!(child.get(20).unwrap(), 2);
+    assert_eq!(child.get(100).unwrap(), 2);
+    assert_eq!(parent.extra_memory(), vec![(20, 1), (100, 1)]);
+}
@@ -0,0 +1,83 @@
+use std::io::Cursor;
+
+use intcode::{serve_gdb, Debugger, Executer, QueueInterface};
+
+// Sends the packets to a stub for the program and returns the replies, in
+// order.
+fn replies(program: &[i128], packets: &[&str]) -> Vec<String> {
+    let executer = Executer::new(0, program, Box::new(QueueInterface::new()), false);
+    let mut debugger = Debugger::new(executer);
+
+    let input: String = packets
+        .iter()
+        .map(|packet| format!("${}#00", packet))
+        .collect();
+    let mut output = Vec::new();
+    serve_gdb(&mut debugger, Cursor::new(input.into_bytes()), &mut output).unwrap();
+
+    String::from_utf8(output)
+        .unwrap()
+        .split('$')
+        .skip(1)
+        .map(|reply| reply.split_once('#').unwrap().0.to_owned())
+        .collect()
+}
+
+fn word(value: i128) -> String {
+    value
+        .to_le_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[test]
+fn counter_and_breakpoints_use_byte_addresses() {
+    // Counts [12] down from 3, the loop starts at word 4.
+    let program = [1101, 3, 0, 12, 1001, 12, -1, 12, 1005, 12, 4, 99, 0];
+    let replies = replies(&program, &["Z0,40,1", "c", "p0", "m40,10", "k"]);
+
+    assert_eq!(replies[0], "OK");
+    assert_eq!(replies[1], "T05swbreak:;");
+    assert_eq!(replies[2], word(4 * 16));
+    // What `x/x $pc` shows, the instruction at the counter.
+    assert_eq!(replies[3], word(1001));
+}
+
+#[test]
+fn unaligned_addresses_are_refused() {
+    let program = [99];
+    let replies = replies(&program, &["Z0,8,1", &format!("P0={}", word(8)), "k"]);
+
+    assert_eq!(replies[0], "E01");
+    assert_eq!(replies[1], "E01");
+}
+
+#[test]
+fn lengths_and_addresses_are_checked() {
+    let max = format!("{:x}", i128::MAX);
+    let packets = [
+        format!("m{},10", max),
+        "mffffffff,ffffffff".to_owned(),
+        format!("M{},2:0000", max),
+        format!("Z2,{},10", max),
+        format!("qXfer:features:read:target.xml:{},{}", max, max),
+        format!("qXfer:features:read:target.xml:0,{}", max),
+        format!("Ga{}", "é".repeat(20)),
+        "é".to_owned(),
+        "k".to_owned(),
+    ];
+    let packets: Vec<&str> = packets.iter().map(String::as_str).collect();
+    let replies = replies(&[99], &packets);
+
+    // Nothing past the end of the address space.
+    assert_eq!(replies[0], "");
+    // One reply fits into the packet size of 0x1000.
+    assert_eq!(replies[1].len(), 0x1000);
+    assert_eq!(replies[2], "E01");
+    assert_eq!(replies[3], "OK");
+    assert_eq!(replies[4], "l");
+    assert!(replies[5].starts_with("l<?xml"));
+    assert_eq!(replies[6], "E01");
+    assert_eq!(replies[7], "");
+}
@@ -0,0 +1,110 @@
+use intcode::{
+    optimize, parse_file, parse_transcript, verify_optimization, Executer, PatchedRun,
+    QueueInterface, ReplayInterface, RunOutcome,
+};
+
+fn day(name: &str) -> Vec<i128> {
+    parse_file(&format!("{}/../{}/input", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
+
+fn verify(program: &[i128], inputs: &[i128]) -> Vec<i128> {
+    let optimized = optimize(program);
+    verify_optimization(program, &optimized.program, inputs, 1_000_000)
+        .unwrap_or_else(|error| panic!("{}", error.reason))
+}
+
+#[test]
+fn second_arb_moves_the_stack_back() {
+    // Sets the base to 100 and back to 0, so the relative write hits [20].
+    let mut program = vec![
+        109, 100, 109, -100, 21101, 7, 0, 20, 1, 20, 21, 22, 4, 22, 99,
+    ];
+    program.resize(30, 0);
+
+    assert_eq!(verify(&program, &[]), vec![7]);
+}
+
+#[test]
+fn negative_offset_reaches_below_the_base() {
+    // The base is 30, the relative write goes to [20].
+    let mut program = vec![109, 30, 21101, 7, 0, -10, 1, 20, 21, 22, 4, 22, 99];
+    program.resize(40, 0);
+
+    assert_eq!(verify(&program, &[]), vec![7]);
+}
+
+#[test]
+fn overflowing_constants_are_left_to_the_run() {
+    let program = [1101, i128::MAX, 1, 9, 4, 9, 99, 0, 0, 0];
+    let optimized = optimize(&program);
+
+    assert_eq!(verify(&program, &[]), vec![]);
+
+    let mut executer = Executer::new(
+        0,
+        &optimized.program,
+        Box::new(QueueInterface::new()),
+        false,
+    );
+    match executer.execute() {
+        RunOutcome::Failed(error) => assert_eq!(error.message, "Arithmetic overflow!"),
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+}
+
+#[test]
+fn endless_runs_are_not_verified() {
+    let program = [1105, 1, 0];
+    let optimized = optimize(&program);
+
+    let error = verify_optimization(&program, &optimized.program, &[], 100).unwrap_err();
+    assert_eq!(error.message, "Run stopped!");
+}
+
+#[test]
+fn day_02_keeps_its_result() {
+    let mut program = day("day-02");
+    program[1] = 12;
+    program[2] = 2;
+    let optimized = optimize(&program).program;
+
+    let run = |program: &[i128]| PatchedRun::new(program).read(&[0]).run().unwrap().values;
+    assert_eq!(run(&optimized), run(&program));
+}
+
+#[test]
+fn day_05_keeps_its_diagnostics() {
+    let program = day("day-05");
+    for input in &[1, 5] {
+        verify(&program, &[*input]);
+    }
+}
+
+#[test]
+fn day_07_keeps_its_amplifier() {
+    let program = day("day-07");
+    for phase in 0..10 {
+        verify(&program, &[phase, 3]);
+    }
+}
+
+#[test]
+fn day_09_keeps_its_answers() {
+    let program = day("day-09");
+    assert_eq!(verify(&program, &[1]), vec![4_006_117_640]);
+    assert_eq!(verify(&program, &[2]), vec![88231]);
+}
+
+#[test]
+fn day_11_replays_its_transcript() {
+    let optimized = optimize(&day("day-11")).program;
+    let path = format!(
+        "{}/../day-11/transcripts/painting.transcript",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let events = parse_transcript(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+    let interface = ReplayInterface::new(events);
+    let mut executer = Executer::new(0, &optimized, Box::new(interface), false);
+    assert!(matches!(executer.execute(), RunOutcome::Halted));
+}
@@ -0,0 +1,48 @@
+use intcode::{parse_file, PatchedRun};
+
+#[test]
+fn day_02_noun_and_verb() {
+    let program = parse_file(&format!("{}/../day-02/input", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let found = PatchedRun::new(&program)
+        .read(&[0])
+        .max_steps(100_000)
+        .search(&[(1, 0..=99), (2, 0..=99)], 4, |result| {
+            result.values[0] == 19_690_720
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(100 * found.patches[0].1 + found.patches[1].1, 2347);
+}
+
+#[test]
+fn runs_that_loop_forever_are_skipped() {
+    // [1] != 0 jumps back to the start forever, 0 outputs [1] and halts.
+    let program = [1105, 0, 0, 4, 1, 99];
+    let search = |values| {
+        PatchedRun::new(&program)
+            .max_steps(1_000)
+            .search(&[(1, values)], 2, |_| true)
+            .unwrap()
+    };
+
+    assert!(search(1..=5).is_none());
+    let found = search(0..=5).unwrap();
+    assert_eq!(found.patches, vec![(1, 0)]);
+    assert_eq!(found.result.output, vec![0]);
+
+    assert!(PatchedRun::new(&program)
+        .patch(1, 1)
+        .max_steps(1_000)
+        .run()
+        .is_err());
+}
+
+#[test]
+fn grid_beyond_64_bits_is_refused() {
+    let grid = [(0, 0..=i128::MAX)];
+    assert!(PatchedRun::new(&[99]).search(&grid, 1, |_| true).is_err());
+
+    let grid = [(0, 0..=u32::MAX as i128), (1, 0..=u32::MAX as i128)];
+    assert!(PatchedRun::new(&[99]).search(&grid, 1, |_| true).is_err());
+}
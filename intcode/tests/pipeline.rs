@@ -0,0 +1,56 @@
+use intcode::{Pipeline, PipelineMode};
+
+// Reads one value, outputs it incremented and halts.
+const INCREMENT: [i128; 9] = [3, 0, 1001, 0, 1, 0, 4, 0, 99];
+
+// The feedback loop example of day 7, 139629729 for phases 9, 8, 7, 6, 5.
+const FEEDBACK: [i128; 29] = [
+    3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28, 1005,
+    28, 6, 99, 0, 0, 5,
+];
+
+#[test]
+fn linear_stages_pass_their_output_on() {
+    let pipeline = Pipeline::new(&INCREMENT, PipelineMode::Linear)
+        .stage(&[5])
+        .stage(&[])
+        .stage(&[]);
+
+    assert_eq!(pipeline.len(), 3);
+    assert_eq!(pipeline.run(0).unwrap(), 6);
+    assert_eq!(pipeline.run(2).unwrap(), 8);
+}
+
+#[test]
+fn feedback_stages_form_a_ring() {
+    let pipeline = [9, 8, 7, 6, 5].iter().enumerate().fold(
+        Pipeline::new(&FEEDBACK, PipelineMode::Feedback),
+        |pipeline, (index, phase)| {
+            if index == 0 {
+                pipeline.stage(&[*phase, 0])
+            } else {
+                pipeline.stage(&[*phase])
+            }
+        },
+    );
+
+    assert_eq!(pipeline.run(4).unwrap(), 139_629_729);
+}
+
+#[test]
+fn pipeline_without_stages_is_an_error() {
+    let pipeline = Pipeline::new(&INCREMENT, PipelineMode::Linear);
+
+    assert!(pipeline.is_empty());
+    assert_eq!(
+        pipeline.run(0).unwrap_err().message,
+        "Unknown pipeline stage!"
+    );
+}
+
+#[test]
+fn stage_without_output_is_an_error() {
+    let pipeline = Pipeline::new(&[99], PipelineMode::Linear).stage(&[]);
+
+    assert_eq!(pipeline.run(0).unwrap_err().message, "No pipeline output!");
+}
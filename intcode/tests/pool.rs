@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use intcode::{Machine, MachineInterface, MachineResult, QueueInterface, RunOutcome, WorkerPool};
+
+struct PanicInterface;
+
+impl MachineInterface for PanicInterface {
+    fn send(&mut self, _value: i128) -> MachineResult<()> {
+        Ok(())
+    }
+
+    fn receive(&mut self) -> MachineResult<Option<i128>> {
+        panic!("interface broke");
+    }
+}
+
+#[test]
+fn panicking_job_fails_its_handle_and_keeps_the_worker() {
+    let pool = Arc::new(WorkerPool::new(1));
+    let mut machine = Machine::with_pool(&[3, 0, 4, 0, 99], pool);
+
+    match machine.spawn(Box::new(PanicInterface)).join() {
+        RunOutcome::Failed(error) => assert_eq!(error.message, "Executer panicked!"),
+        outcome => panic!("expected a failure, got {:?}", outcome),
+    }
+
+    let interface = QueueInterface::new();
+    interface.push_input(&[7]);
+    let handle = machine.spawn(Box::new(interface.clone()));
+    assert!(matches!(handle.join(), RunOutcome::Halted));
+    assert_eq!(interface.take_output(), vec![7]);
+}
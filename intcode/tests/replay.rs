@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use intcode::{
+    parse_file, parse_transcript, Machine, ReplayInterface, RunOutcome, TranscriptEvent,
+};
+
+fn day(path: &str) -> String {
+    format!("{}/../{}", env!("CARGO_MANIFEST_DIR"), path)
+}
+
+fn replay(program: &str, transcript: &str) -> Vec<TranscriptEvent> {
+    let program = parse_file(&day(program)).unwrap();
+    let events = parse_transcript(&std::fs::read_to_string(day(transcript)).unwrap()).unwrap();
+
+    let interface = ReplayInterface::new(events.clone());
+    match Machine::new(&program).spawn(Box::new(interface)).join() {
+        RunOutcome::Halted => {}
+        outcome => panic!("{} replayed as {:?}", transcript, outcome),
+    }
+    events
+}
+
+fn outputs(events: &[TranscriptEvent]) -> Vec<i128> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            TranscriptEvent::Output(value) => Some(*value),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn day_07_amplifiers() {
+    let mut last = None;
+    for stage in 0..5 {
+        let transcript = format!("day-07/transcripts/stage-{}.transcript", stage);
+        last = outputs(&replay("day-07/input", &transcript)).pop();
+    }
+    assert_eq!(last, Some(19_741_286));
+}
+
+#[test]
+fn day_11_painting_robot() {
+    let events = replay("day-11/input", "day-11/transcripts/painting.transcript");
+
+    let mut position = (0, 0);
+    let mut direction = 0;
+    let mut painted = HashSet::new();
+    for pair in outputs(&events).chunks(2) {
+        painted.insert(position);
+        direction = (direction + if pair[1] == 0 { 3 } else { 1 }) % 4;
+        position = match direction {
+            0 => (position.0, position.1 + 1),
+            1 => (position.0 + 1, position.1),
+            2 => (position.0, position.1 - 1),
+            _ => (position.0 - 1, position.1),
+        };
+    }
+    assert_eq!(painted.len(), 249);
+}
+
+#[test]
+fn replay_reports_a_changed_output() {
+    let events = vec![TranscriptEvent::Input(5), TranscriptEvent::Output(6)];
+    let interface = ReplayInterface::new(events);
+
+    match Machine::new(&[3, 0, 4, 0, 99])
+        .spawn(Box::new(interface))
+        .join()
+    {
+        RunOutcome::Failed(error) => assert_eq!(error.message, "Replay mismatch!"),
+        outcome => panic!("expected a mismatch, got {:?}", outcome),
+    }
+}
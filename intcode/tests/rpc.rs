@@ -0,0 +1,88 @@
+use std::io::Cursor;
+
+use intcode::{serve_rpc, Json};
+
+// Serves the requests, one per line, and returns the results in order.
+fn results(requests: &[&str]) -> Vec<Json> {
+    responses(requests, true)
+        .iter()
+        .map(|response| response.get("result").clone())
+        .collect()
+}
+
+fn responses(requests: &[&str], files: bool) -> Vec<Json> {
+    let mut output = Vec::new();
+    serve_rpc(Cursor::new(requests.join("\n")), &mut output, files).unwrap();
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| Json::parse(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn run_without_a_budget_returns_on_an_endless_loop() {
+    let results = results(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"load","params":{"program":[1105,1,0]}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"run","params":{"machine":0}}"#,
+    ]);
+
+    assert_eq!(results[1].get("outcome").as_str(), Some("paused"));
+    assert_eq!(results[1].get("counter").as_i128(), Some(0));
+}
+
+#[test]
+fn run_with_no_steps_does_not_execute() {
+    let results = results(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"load","params":{"program":"1101,1,1,0,99"}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"run","params":{"machine":0,"maxSteps":0}}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"run","params":{"machine":0}}"#,
+    ]);
+
+    assert_eq!(results[1].get("outcome").as_str(), Some("paused"));
+    assert_eq!(results[1].get("counter").as_i128(), Some(0));
+    assert_eq!(results[2].get("outcome").as_str(), Some("halted"));
+}
+
+#[test]
+fn inspect_memory_checks_its_range() {
+    let end = format!(
+        r#"{{"jsonrpc":"2.0","id":2,"method":"inspectMemory","params":{{"machine":0,"address":{},"count":2}}}}"#,
+        i128::MAX
+    );
+    let responses = responses(
+        &[
+            r#"{"jsonrpc":"2.0","id":1,"method":"load","params":{"program":[99]}}"#,
+            &end,
+            r#"{"jsonrpc":"2.0","id":3,"method":"inspectMemory","params":{"machine":0,"address":0,"count":1000000}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"inspectMemory","params":{"machine":0,"address":0,"count":2}}"#,
+        ],
+        true,
+    );
+
+    assert_eq!(
+        responses[1].get("error").get("code").as_i128(),
+        Some(-32602)
+    );
+    assert_eq!(
+        responses[2].get("error").get("code").as_i128(),
+        Some(-32602)
+    );
+    let values = responses[3].get("result").get("values").as_array().unwrap();
+    assert_eq!(values, &[Json::from(99), Json::from(0)]);
+}
+
+#[test]
+fn load_by_path_needs_files() {
+    let path = format!("{}/../day-02/input", env!("CARGO_MANIFEST_DIR"));
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"load","params":{{"path":{:?}}}}}"#,
+        path
+    );
+
+    let allowed = responses(&[&request], true);
+    assert_eq!(allowed[0].get("result").get("machine").as_i128(), Some(0));
+
+    let refused = responses(&[&request], false);
+    assert_eq!(refused[0].get("error").get("code").as_i128(), Some(-32602));
+}
@@ -0,0 +1,45 @@
+use intcode::{Search, SearchOrder};
+
+// Adds every input to [101] and outputs the sum. An input of 0 makes it
+// loop forever without asking for more.
+const WALK: [i128; 18] = [
+    3, 100, 1005, 100, 9, 1105, 1, 5, 99, 1, 101, 100, 101, 4, 101, 1105, 1, 0,
+];
+
+// The position after a move, positions beyond 10 lead nowhere.
+fn read(_: &i128, _: i128, outputs: &[i128]) -> Option<i128> {
+    outputs.last().copied().filter(|position| *position <= 10)
+}
+
+#[test]
+fn shortest_path_to_a_reachable_goal() {
+    let found = Search::new(&WALK, &[2, 3])
+        .run(0, read, |position| *position == 7)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(found.inputs.len(), 3);
+    assert_eq!(found.inputs.iter().sum::<i128>(), 7);
+}
+
+#[test]
+fn unreachable_goal_ends_the_search() {
+    for order in [SearchOrder::BreadthFirst, SearchOrder::DepthFirst] {
+        let found = Search::new(&WALK, &[2, 4])
+            .order(order)
+            .run(0, read, |position| *position == 7)
+            .unwrap();
+        assert!(found.is_none());
+    }
+}
+
+#[test]
+fn moves_that_never_return_are_dead_branches() {
+    let found = Search::new(&WALK, &[0, 3])
+        .max_steps(1_000)
+        .run(0, read, |position| *position == 6)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(found.inputs, vec![3, 3]);
+}
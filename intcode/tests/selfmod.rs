@@ -0,0 +1,50 @@
+use intcode::{CodeWrite, CodeWriteKind, Executer, QueueInterface, RunOutcome, SelfModification};
+
+fn events(program: &[i128]) -> Vec<CodeWrite> {
+    let detector = SelfModification::new();
+    let interface = QueueInterface::new();
+    let mut executer = Executer::new(0, program, Box::new(interface), false);
+    executer.add_observer(Box::new(detector.clone()));
+
+    assert!(matches!(executer.execute(), RunOutcome::Halted));
+    detector.events()
+}
+
+#[test]
+fn write_to_an_operand_decoded_later() {
+    // Patches the operand of `out 0` at 4 to 2 before it runs.
+    let events = events(&[1101, 1, 1, 5, 104, 0, 99]);
+
+    assert_eq!(
+        events,
+        vec![CodeWrite {
+            counter: 0,
+            address: 5,
+            old: 0,
+            value: 2,
+            kind: CodeWriteKind::DecodedLater { counter: 4 },
+        }]
+    );
+}
+
+#[test]
+fn write_to_executed_code_is_reported_once() {
+    // Overwrites its own first opcode with `hlt` and jumps back to it.
+    let events = events(&[1101, 0, 99, 0, 1105, 1, 0]);
+
+    assert_eq!(
+        events,
+        vec![CodeWrite {
+            counter: 0,
+            address: 0,
+            old: 1101,
+            value: 99,
+            kind: CodeWriteKind::ExecutedBefore,
+        }]
+    );
+}
+
+#[test]
+fn program_without_code_writes_is_static() {
+    assert!(events(&[1101, 1, 1, 7, 4, 7, 99, 0]).is_empty());
+}
@@ -0,0 +1,59 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use intcode::{Executer, RunOutcome, TcpInterface};
+
+// Runs the program against a peer on the loopback interface that sends
+// `input` and reads until the end of the stream.
+fn run(program: &[i128], input: &str, ascii: bool) -> (RunOutcome, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let input = input.to_owned();
+    let peer = std::thread::spawn(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        // A stream that is never shut down fails the test instead of
+        // hanging it.
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        stream.write_all(input.as_bytes()).unwrap();
+
+        let mut output = String::new();
+        stream.read_to_string(&mut output).unwrap();
+        output
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    let interface = TcpInterface::new(stream, ascii).unwrap();
+    // The executer still holds the stream while the peer reads.
+    let mut executer = Executer::new(0, program, Box::new(interface), false);
+    let outcome = executer.execute();
+    (outcome, peer.join().unwrap())
+}
+
+#[test]
+fn halted_program_closes_the_stream() {
+    let (outcome, output) = run(&[3, 0, 1002, 0, 2, 0, 4, 0, 99], "21\n", false);
+    assert!(matches!(outcome, RunOutcome::Halted));
+    assert_eq!(output, "42\n");
+}
+
+#[test]
+fn failed_program_closes_the_stream() {
+    let (outcome, output) = run(&[104, 7, 0], "", false);
+    assert!(matches!(outcome, RunOutcome::Failed(_)));
+    assert_eq!(output, "7\n");
+}
+
+#[test]
+fn ascii_mode_sends_lines_as_text() {
+    // Echoes the first line, then prints 1000.
+    let program = [
+        3, 100, 4, 100, 1008, 100, 10, 101, 1006, 101, 0, 104, 1000, 99,
+    ];
+    let (outcome, output) = run(&program, "hi\n", true);
+    assert!(matches!(outcome, RunOutcome::Halted));
+    assert_eq!(output, "hi\n1000\n");
+}
@@ -0,0 +1,27 @@
+use intcode::Topology;
+
+// Reads one value, outputs it incremented and halts.
+const INCREMENT: [i128; 9] = [3, 0, 1001, 0, 1, 0, 4, 0, 99];
+
+#[test]
+fn ring_with_a_seed_passes_values_on() {
+    let topology = Topology::parse("node A 1\nnode B\nedge A -> B -> A").unwrap();
+
+    let last_outputs = topology.run(&INCREMENT, 0).unwrap();
+    assert_eq!(last_outputs, vec![Some(2), Some(3)]);
+}
+
+#[test]
+fn ring_without_input_is_a_deadlock() {
+    let topology = Topology::parse("node A\nnode B\nnode C\nedge A -> B -> C -> A").unwrap();
+
+    let error = topology.run(&INCREMENT, 0).unwrap_err();
+    assert_eq!(error.message, "Network deadlock!");
+}
+
+#[test]
+fn node_without_sources_runs_out_of_input() {
+    let topology = Topology::parse("node A\nnode B\nedge A -> B").unwrap();
+
+    assert_eq!(topology.run(&INCREMENT, 0).unwrap(), vec![None, None]);
+}